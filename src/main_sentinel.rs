@@ -5,57 +5,49 @@
 ///
 /// The server runs four concurrent tasks:
 /// - gRPC server: Receives reports from clients
-/// - State manager: Processes reports and cleans up offline clients  
+/// - State actor: Owns the state, applies reports and cleans up offline
+///   clients, publishing snapshots for the other tasks to read
 /// - TUI: Terminal interface for real-time monitoring
 /// - Web server: HTTP API and dashboard
+///
+/// An alert notifier also runs alongside them when `SWARM_ALERT_WEBHOOK` is set,
+/// a federation poller when `SWARM_FEDERATION_CHILDREN` is set, and
+/// replication and leader election when `SWARM_HA_PEER` is set.
+///
+/// `sentinel bench [nodes] [seconds] [interval_ms]` benchmarks the state
+/// actor with simulated nodes instead.
 mod sentinel;
 
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tokio::sync::broadcast;
-
 use sentinel::{
-    grpc_server::{Sentinel, run_grpc_server},
+    alerts::run_alert_notifier,
+    bench::run_bench,
+    federation::run_federation,
+    grpc_server::{Sentinel, grpc_port, run_grpc_server},
+    ha::spawn_ha,
+    state::spawn_state_actor,
     tui::run_tui_display_only,
-    types::{App, ReportEntry, SharedState},
-    web::run_web_server,
+    types::App,
+    web::{run_web_server, web_port},
 };
 
 pub mod swarmreport {
     tonic::include_proto!("swarmreport");
 }
 
-/// Manages incoming reports and periodically cleans up offline clients
-async fn run_state_manager(
-    mut report_receiver: broadcast::Receiver<ReportEntry>,
-    state: SharedState,
-) {
-    let mut last_cleanup = std::time::Instant::now();
-
-    loop {
-        // Process all pending reports
-        while let Ok(report) = report_receiver.try_recv() {
-            state.lock().unwrap().update_report(report);
-        }
-
-        // Clean up offline clients every 5 seconds
-        if last_cleanup.elapsed() >= Duration::from_secs(5) {
-            state.lock().unwrap().remove_offline_clients(60);
-            last_cleanup = std::time::Instant::now();
-        }
-
-        tokio::time::sleep(Duration::from_millis(100)).await;
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Create communication channel for reports
-    let (report_sender, report_receiver) = broadcast::channel::<ReportEntry>(100);
-    let sentinel = Sentinel { report_sender };
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "bench") {
+        run_bench(&args[1..]).await;
+        return Ok(());
+    }
 
-    // Create shared state for all components
-    let shared_state = Arc::new(Mutex::new(App::new()));
+    // Start the state actor; every component talks to it through a handle
+    let state = spawn_state_actor(App::new());
+    let sentinel = Sentinel {
+        state: state.clone(),
+        replicator: spawn_ha(state.clone()).await,
+    };
 
     // Spawn all concurrent tasks
     let server_handle = tokio::spawn({
@@ -63,31 +55,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         async move { run_grpc_server(sentinel).await }
     });
 
-    let state_manager_handle = tokio::spawn({
-        let state = shared_state.clone();
-        async move { run_state_manager(report_receiver, state).await }
-    });
-
     let tui_handle = tokio::spawn({
-        let state = shared_state.clone();
+        let state = state.clone();
         async move { run_tui_display_only(state).await }
     });
 
     let web_handle = tokio::spawn({
-        let state = shared_state.clone();
+        let state = state.clone();
         async move { run_web_server(state).await }
     });
 
+    // Not awaited below: they return immediately when not configured
+    tokio::spawn(run_alert_notifier(state.clone()));
+    tokio::spawn(run_federation(state));
+
     // Print startup information
     println!("SwarmReport Sentinel 0.1 - TUI + Web Mode");
-    println!("Starting gRPC server on 0.0.0.0:50051");
-    println!("Web dashboard available at http://localhost:6969");
+    println!("Starting gRPC server on 0.0.0.0:{}", grpc_port());
+    println!("Web dashboard available at http://localhost:{}", web_port());
     println!("Press 'q' in TUI to quit. Clients auto-remove after 60s offline.");
 
     // Wait for any task to complete (which means exit)
     tokio::select! {
         _ = server_handle => {},
-        _ = state_manager_handle => {},
         _ = tui_handle => {},
         _ = web_handle => {},
     }
@@ -0,0 +1,351 @@
+/// Offline report buffering
+///
+/// Reports that could not be delivered are kept, oldest first, and replayed
+/// once the sentinel is reachable again. When `SWARM_SPOOL_DIR` is set every
+/// report is written there as it is buffered and deleted once delivered, so
+/// the whole backlog survives reporter restarts; the spool is bounded by
+/// `SWARM_SPOOL_MAX_MB`. Otherwise up to `SWARM_BUFFER_REPORTS` reports are
+/// held in memory and the oldest are dropped beyond that.
+///
+/// The sentinel keeps only each node's latest report, so replaying the
+/// backlog completes the node's sequence of reports there but doesn't add
+/// history for the time it was unreachable.
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+
+use crate::swarmreport::SystemReport;
+
+/// Spool files are named `<collected_at millis>-<counter>.pb`, zero-padded so
+/// sorting by name is sorting by age
+const SPOOL_EXTENSION: &str = "pb";
+
+/// On-disk buffer, used instead of memory when configured
+struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    files: VecDeque<(PathBuf, u64)>,
+    bytes: u64,
+    counter: u64,
+}
+
+impl Spool {
+    /// Opens the spool directory, picking up reports left by a previous run
+    fn open(dir: &Path, max_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut files: Vec<(PathBuf, u64)> = std::fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == SPOOL_EXTENSION))
+            .map(|path| {
+                let size = std::fs::metadata(&path).map_or(0, |m| m.len());
+                (path, size)
+            })
+            .collect();
+        files.sort();
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            bytes: files.iter().map(|(_, size)| size).sum(),
+            files: files.into(),
+            counter: 0,
+        })
+    }
+
+    fn push(&mut self, report: &SystemReport) {
+        let data = report.encode_to_vec();
+        let size = data.len() as u64;
+
+        // Make room by dropping the oldest spooled reports
+        while self.bytes + size > self.max_bytes && !self.files.is_empty() {
+            self.remove_oldest();
+        }
+        if size > self.max_bytes {
+            return;
+        }
+
+        self.counter += 1;
+        let path = self.dir.join(format!(
+            "{:020}-{:010}.{SPOOL_EXTENSION}",
+            report.collected_at.max(0),
+            self.counter
+        ));
+        match std::fs::write(&path, data) {
+            Ok(()) => {
+                self.files.push_back((path, size));
+                self.bytes += size;
+            }
+            Err(e) => eprintln!("Failed to spool report to {}: {e}", path.display()),
+        }
+    }
+
+    /// Reads the oldest spooled report, discarding files that can't be decoded
+    fn oldest(&mut self) -> Option<SystemReport> {
+        while let Some((path, _)) = self.files.front() {
+            match std::fs::read(path)
+                .ok()
+                .and_then(|data| SystemReport::decode(data.as_slice()).ok())
+            {
+                Some(report) => return Some(report),
+                None => self.remove_oldest(),
+            }
+        }
+        None
+    }
+
+    fn remove_oldest(&mut self) {
+        if let Some((path, size)) = self.files.pop_front() {
+            let _ = std::fs::remove_file(path);
+            self.bytes = self.bytes.saturating_sub(size);
+        }
+    }
+}
+
+/// Reports waiting to be delivered, oldest first, held in the spool when
+/// there is one and in memory otherwise
+pub struct ReportBuffer {
+    memory: VecDeque<SystemReport>,
+    capacity: usize,
+    spool: Option<Spool>,
+    dropped: u64,
+}
+
+impl ReportBuffer {
+    /// Creates a buffer; `spool_subdir` separates the spools of buffers that
+    /// share `SWARM_SPOOL_DIR`
+    pub fn from_env(spool_subdir: Option<&str>) -> Self {
+        let capacity = std::env::var("SWARM_BUFFER_REPORTS")
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(1200);
+        let max_mb: u64 = std::env::var("SWARM_SPOOL_MAX_MB")
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(256);
+
+        let spool = std::env::var("SWARM_SPOOL_DIR")
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| match spool_subdir {
+                Some(subdir) => Path::new(&dir).join(subdir).display().to_string(),
+                None => dir,
+            })
+            .and_then(|dir| match Spool::open(Path::new(&dir), max_mb << 20) {
+                Ok(spool) => {
+                    println!(
+                        "Spooling undelivered reports to {dir} ({} pending)",
+                        spool.files.len()
+                    );
+                    Some(spool)
+                }
+                Err(e) => {
+                    eprintln!("Report spool disabled, cannot use {dir}: {e}");
+                    None
+                }
+            });
+
+        Self {
+            memory: VecDeque::new(),
+            capacity,
+            spool,
+            dropped: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.memory.len() + self.spool.as_ref().map_or(0, |spool| spool.files.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Buffers an undelivered report, dropping the oldest one held in
+    /// memory once the buffer is full
+    pub fn push(&mut self, report: SystemReport) {
+        if let Some(spool) = &mut self.spool {
+            spool.push(&report);
+            return;
+        }
+
+        self.memory.push_back(report);
+        while self.memory.len() > self.capacity {
+            self.memory.pop_front();
+            self.dropped += 1;
+            if self.dropped == 1 || self.dropped.is_multiple_of(100) {
+                eprintln!("Report buffer full, {} reports dropped", self.dropped);
+            }
+        }
+    }
+
+    /// Returns a copy of the oldest buffered report
+    pub fn oldest(&mut self) -> Option<SystemReport> {
+        match &mut self.spool {
+            Some(spool) => spool.oldest(),
+            None => self.memory.front().cloned(),
+        }
+    }
+
+    /// Removes the oldest buffered report once it has been delivered
+    pub fn remove_oldest(&mut self) {
+        match &mut self.spool {
+            Some(spool) => spool.remove_oldest(),
+            None => {
+                self.memory.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("swarmreport-buffer-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn report(collected_at: i64) -> SystemReport {
+        SystemReport {
+            collected_at,
+            ..Default::default()
+        }
+    }
+
+    fn buffer(capacity: usize, spool: Option<Spool>) -> ReportBuffer {
+        ReportBuffer {
+            memory: VecDeque::new(),
+            capacity,
+            spool,
+            dropped: 0,
+        }
+    }
+
+    /// Delivers everything buffered, returning the collection times in order
+    fn drain(buffer: &mut ReportBuffer) -> Vec<i64> {
+        let mut delivered = Vec::new();
+        while let Some(report) = buffer.oldest() {
+            delivered.push(report.collected_at);
+            buffer.remove_oldest();
+        }
+        delivered
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn spool_files_sort_by_collection_time() {
+        let dir = TempDir::new("names");
+        let mut spool = Spool::open(&dir.0, 1 << 20).unwrap();
+        for collected_at in [1_700_000_000_000, 5, 1_700_000_000_000] {
+            spool.push(&report(collected_at));
+        }
+
+        assert_eq!(
+            file_names(&dir.0),
+            [
+                "00000000000000000005-0000000002.pb",
+                "00000001700000000000-0000000001.pb",
+                "00000001700000000000-0000000003.pb",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_report_is_spooled_and_survives_a_restart() {
+        let dir = TempDir::new("restart");
+        let mut first_run = buffer(1, Some(Spool::open(&dir.0, 1 << 20).unwrap()));
+        for collected_at in [10, 20, 30] {
+            first_run.push(report(collected_at));
+        }
+        // Nothing is held only in memory, even past the memory capacity
+        assert!(first_run.memory.is_empty());
+        assert_eq!(file_names(&dir.0).len(), 3);
+        drop(first_run);
+
+        let mut second_run = buffer(1, Some(Spool::open(&dir.0, 1 << 20).unwrap()));
+        assert_eq!(second_run.len(), 3);
+        second_run.push(report(40));
+        assert_eq!(drain(&mut second_run), [10, 20, 30, 40]);
+        assert!(file_names(&dir.0).is_empty());
+    }
+
+    #[test]
+    fn delivered_reports_are_deleted() {
+        let dir = TempDir::new("delete");
+        let mut buffer = buffer(10, Some(Spool::open(&dir.0, 1 << 20).unwrap()));
+        buffer.push(report(10));
+        buffer.push(report(20));
+
+        assert_eq!(buffer.oldest().map(|report| report.collected_at), Some(10));
+        // Reading doesn't consume; only a delivery does
+        assert_eq!(file_names(&dir.0).len(), 2);
+        buffer.remove_oldest();
+        assert_eq!(file_names(&dir.0).len(), 1);
+        assert_eq!(buffer.len(), 1);
+    }
+
+    #[test]
+    fn corrupt_spool_files_are_skipped() {
+        let dir = TempDir::new("corrupt");
+        std::fs::create_dir_all(&dir.0).unwrap();
+        std::fs::write(
+            dir.0.join("00000000000000000001-0000000001.pb"),
+            b"\xff\xff",
+        )
+        .unwrap();
+        std::fs::write(dir.0.join("notes.txt"), "not a report").unwrap();
+
+        let mut buffer = buffer(10, Some(Spool::open(&dir.0, 1 << 20).unwrap()));
+        buffer.push(report(20));
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(drain(&mut buffer), [20]);
+        assert_eq!(file_names(&dir.0), ["notes.txt"]);
+    }
+
+    #[test]
+    fn full_spool_drops_the_oldest_reports() {
+        let dir = TempDir::new("limit");
+        let size = report(10).encode_to_vec().len() as u64;
+        let mut buffer = buffer(10, Some(Spool::open(&dir.0, size * 2).unwrap()));
+        for collected_at in [10, 20, 30] {
+            buffer.push(report(collected_at));
+        }
+        assert_eq!(drain(&mut buffer), [20, 30]);
+    }
+
+    #[test]
+    fn full_memory_buffer_drops_the_oldest_reports() {
+        let mut buffer = buffer(2, None);
+        for collected_at in [10, 20, 30] {
+            buffer.push(report(collected_at));
+        }
+        assert_eq!(buffer.dropped, 1);
+        assert_eq!(drain(&mut buffer), [20, 30]);
+        assert!(buffer.is_empty());
+    }
+}
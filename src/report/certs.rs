@@ -0,0 +1,270 @@
+/// TLS certificate expiry checks
+///
+/// Reads the leaf certificate served by each endpoint in
+/// `SWARM_CERT_ENDPOINTS` (`host[:port]`, port 443 by default) and the first
+/// certificate in each PEM or DER file in `SWARM_CERT_FILES`, every
+/// `SWARM_CERT_CHECK_INTERVAL` seconds. Certificates are read without
+/// verification so expired and self-signed ones are still reported.
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{CryptoProvider, ring};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::Pem;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::report::config::{env_list, env_secs};
+use crate::swarmreport::Certificate;
+
+/// Latest certificates, in configuration order
+pub type SharedCertificates = Arc<Mutex<Vec<Certificate>>>;
+
+/// Time allowed for connecting to an endpoint and completing the handshake
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Accepts any certificate; the check only reads it
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn connector() -> Result<TlsConnector, String> {
+    let provider = Arc::new(ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Fills in the certificate fields from DER data
+fn describe(source: &str, der: &[u8]) -> Certificate {
+    let mut certificate = Certificate {
+        source: source.to_string(),
+        checked_at: now(),
+        ..Default::default()
+    };
+
+    let parsed = match X509Certificate::from_der(der) {
+        Ok((_, parsed)) => parsed,
+        Err(e) => {
+            certificate.error = format!("invalid certificate: {e}");
+            return certificate;
+        }
+    };
+
+    certificate.subject = parsed.subject().to_string();
+    certificate.issuer = parsed.issuer().to_string();
+    certificate.not_before = parsed.validity().not_before.timestamp();
+    certificate.not_after = parsed.validity().not_after.timestamp();
+    if let Ok(Some(san)) = parsed.subject_alternative_name() {
+        certificate.sans = san
+            .value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some(dns.to_string()),
+                GeneralName::IPAddress(bytes) => match bytes.len() {
+                    4 => <[u8; 4]>::try_from(*bytes)
+                        .ok()
+                        .map(|ip| std::net::Ipv4Addr::from(ip).to_string()),
+                    16 => <[u8; 16]>::try_from(*bytes)
+                        .ok()
+                        .map(|ip| std::net::Ipv6Addr::from(ip).to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+    }
+    certificate
+}
+
+fn failed(source: &str, error: String) -> Certificate {
+    Certificate {
+        source: source.to_string(),
+        error,
+        checked_at: now(),
+        ..Default::default()
+    }
+}
+
+/// Splits `host[:port]`, keeping bracketed IPv6 addresses intact
+fn split_endpoint(endpoint: &str) -> (String, u16) {
+    if let Some(rest) = endpoint.strip_prefix('[')
+        && let Some((host, port)) = rest.split_once(']')
+    {
+        let port = port.strip_prefix(':').and_then(|p| p.parse().ok());
+        return (host.to_string(), port.unwrap_or(443));
+    }
+    match endpoint.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => {
+            (host.to_string(), port.parse().unwrap_or(443))
+        }
+        _ => (endpoint.to_string(), 443),
+    }
+}
+
+async fn check_endpoint(connector: &TlsConnector, endpoint: &str) -> Certificate {
+    let (host, port) = split_endpoint(endpoint);
+    let server_name = match ServerName::try_from(host.clone()) {
+        Ok(name) => name,
+        Err(e) => return failed(endpoint, e.to_string()),
+    };
+
+    let handshake = async {
+        let stream = TcpStream::connect((host.as_str(), port))
+            .await
+            .map_err(|e| e.to_string())?;
+        let tls = connector
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| e.to_string())?;
+        tls.get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .map(|leaf| leaf.to_vec())
+            .ok_or_else(|| "no certificate presented".to_string())
+    };
+
+    match tokio::time::timeout(ENDPOINT_TIMEOUT, handshake).await {
+        Ok(Ok(der)) => describe(endpoint, &der),
+        Ok(Err(e)) => failed(endpoint, e),
+        Err(_) => failed(
+            endpoint,
+            format!("timed out after {}s", ENDPOINT_TIMEOUT.as_secs()),
+        ),
+    }
+}
+
+fn check_file(path: &str) -> Certificate {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) => return failed(path, e.to_string()),
+    };
+
+    let pem = Pem::iter_from_buffer(&data)
+        .filter_map(Result::ok)
+        .find(|pem| pem.label == "CERTIFICATE");
+    match pem {
+        Some(pem) => describe(path, &pem.contents),
+        // Not PEM, try the file as DER
+        None => describe(path, &data),
+    }
+}
+
+/// Starts checking the configured endpoints and files, returning their shared
+/// latest certificates. Setting `SWARM_CERT_CHECK_INTERVAL=0` disables checking.
+pub fn spawn_cert_checker() -> SharedCertificates {
+    start_checker(
+        env_list("SWARM_CERT_ENDPOINTS"),
+        env_list("SWARM_CERT_FILES"),
+        env_secs("SWARM_CERT_CHECK_INTERVAL", 3600),
+    )
+}
+
+/// Checks `endpoints` and `files` every `interval`, never when it is zero
+fn start_checker(
+    endpoints: Vec<String>,
+    files: Vec<String>,
+    interval: Duration,
+) -> SharedCertificates {
+    let certificates = SharedCertificates::default();
+    if interval.is_zero() || (endpoints.is_empty() && files.is_empty()) {
+        return certificates;
+    }
+
+    let connector = match connector() {
+        Ok(connector) => connector,
+        Err(e) => {
+            eprintln!("Certificate checks disabled: {e}");
+            return certificates;
+        }
+    };
+
+    let shared = certificates.clone();
+    tokio::spawn(async move {
+        loop {
+            let mut checked = Vec::with_capacity(endpoints.len() + files.len());
+            for endpoint in &endpoints {
+                checked.push(check_endpoint(&connector, endpoint).await);
+            }
+            for path in &files {
+                checked.push(check_file(path));
+            }
+            *shared.lock().unwrap() = checked;
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    certificates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn zero_interval_disables_checking() {
+        let files = vec!["/nonexistent/cert.pem".to_string()];
+
+        let disabled = start_checker(Vec::new(), files.clone(), Duration::ZERO);
+        let enabled = start_checker(Vec::new(), files, Duration::from_secs(3600));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(disabled.lock().unwrap().is_empty());
+        let checked = enabled.lock().unwrap();
+        assert_eq!(checked.len(), 1);
+        assert!(!checked[0].error.is_empty());
+    }
+}
@@ -1,37 +1,443 @@
-use crate::report::system::get_system_report;
-use crate::swarmreport::SwarmReportRequest;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use tonic::transport::{Channel, Endpoint};
+
+use crate::report::buffer::ReportBuffer;
+use crate::report::config::env_list;
+use crate::report::connection::{ConnectionMonitor, ConnectionState, StatusFile};
+use crate::report::system::{Collector, unix_millis};
+use crate::swarmreport::swarm_report_service_client::SwarmReportServiceClient;
 /// Client functions for communicating with the SwarmReport Sentinel
 ///
 /// These functions handle sending system reports to the sentinel server
 /// and retrieving aggregated swarm information.
-use crate::swarmreport::swarm_report_service_client::SwarmReportServiceClient;
+///
+/// `SWARM_SENTINEL_ADDR` may list several sentinels, separated by commas.
+/// With `SWARM_SENTINEL_MODE=failover` (the default) reports go to the first
+/// reachable sentinel in list order, falling back to the primary as soon as
+/// it answers again. With `SWARM_SENTINEL_MODE=fanout` every sentinel gets
+/// every report, each with its own buffer and backoff, and all of them are
+/// sent to at once so one that is down doesn't delay the others.
+use crate::swarmreport::{SwarmReportRequest, SystemReport};
 
-/// Sends the current system report to the sentinel server
-pub async fn send_system_report() -> Result<(), Box<dyn std::error::Error>> {
-    let report = get_system_report();
+/// Time allowed for connecting to a sentinel and for each call, so one that
+/// hangs doesn't hold up failover to the next
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
-    let server_addr = std::env::var("SWARM_SENTINEL_ADDR")
-        .unwrap_or_else(|_| "http://localhost:50051".to_string());
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryMode {
+    /// Send to the first reachable sentinel
+    Failover,
+    /// Send to every sentinel
+    Fanout,
+}
 
-    let mut client = SwarmReportServiceClient::connect(server_addr).await?;
-    let response = client
-        .send_system_report(tonic::Request::new(report))
+impl DeliveryMode {
+    fn from_env() -> Self {
+        match std::env::var("SWARM_SENTINEL_MODE")
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "fanout" | "fan-out" | "all" => DeliveryMode::Fanout,
+            "" | "failover" => DeliveryMode::Failover,
+            other => {
+                eprintln!("Unknown SWARM_SENTINEL_MODE '{other}', using failover");
+                DeliveryMode::Failover
+            }
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DeliveryMode::Failover => "failover",
+            DeliveryMode::Fanout => "fanout",
+        }
+    }
+}
+
+/// Sentinels sharing one buffer; a report leaves the buffer once any of them
+/// has accepted it
+struct Route {
+    buffer: ReportBuffer,
+    /// In order of preference
+    sentinels: Vec<ConnectionMonitor>,
+    /// Sentinel that accepted the last delivery
+    active: Option<usize>,
+}
+
+impl Route {
+    fn new(buffer: ReportBuffer, addrs: &[String]) -> Self {
+        Self {
+            buffer,
+            sentinels: addrs
+                .iter()
+                .map(|addr| ConnectionMonitor::from_env(addr))
+                .collect(),
+            active: None,
+        }
+    }
+
+    /// Flushes the buffer to the first sentinel that takes it. Sentinels
+    /// still backing off are skipped; when every one is, the report just
+    /// stays buffered.
+    async fn deliver(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let failover = self.sentinels.len() > 1;
+        let mut result = Ok(());
+        for (index, sentinel) in self.sentinels.iter_mut().enumerate() {
+            if !sentinel.ready() {
+                continue;
+            }
+            match flush_buffer(&mut self.buffer, sentinel.sentinel()).await {
+                Ok(()) => {
+                    sentinel.succeeded();
+                    if failover && self.active != Some(index) {
+                        println!("Delivering reports to {}", sentinel.sentinel());
+                    }
+                    self.active = Some(index);
+                    return Ok(());
+                }
+                Err(e) => {
+                    sentinel.failed(&e.to_string());
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Where collected reports go: one route in failover mode, one route per
+/// sentinel in fan-out mode
+pub struct Delivery {
+    mode: DeliveryMode,
+    routes: Vec<Route>,
+    status_file: StatusFile,
+}
+
+impl Delivery {
+    pub fn from_env() -> Self {
+        let mut addrs = env_list("SWARM_SENTINEL_ADDR");
+        if addrs.is_empty() {
+            addrs.push("http://localhost:50051".to_string());
+        }
+        let mode = DeliveryMode::from_env();
+
+        let routes = match mode {
+            DeliveryMode::Failover => vec![Route::new(ReportBuffer::from_env(None), &addrs)],
+            DeliveryMode::Fanout if addrs.len() == 1 => {
+                vec![Route::new(ReportBuffer::from_env(None), &addrs)]
+            }
+            // Each sentinel spools into its own subdirectory
+            DeliveryMode::Fanout => addrs
+                .iter()
+                .map(|addr| {
+                    let subdir: String = addr
+                        .chars()
+                        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                        .collect();
+                    Route::new(
+                        ReportBuffer::from_env(Some(&subdir)),
+                        std::slice::from_ref(addr),
+                    )
+                })
+                .collect(),
+        };
+
+        if addrs.len() > 1 {
+            println!("Sending reports to {} ({})", addrs.join(", "), mode.label());
+        }
+
+        Self {
+            mode,
+            routes,
+            status_file: StatusFile::from_env(),
+        }
+    }
+
+    /// A sentinel the last delivery reached, if any
+    pub fn connected_sentinel(&self) -> Option<&str> {
+        self.routes
+            .iter()
+            .flat_map(|route| &route.sentinels)
+            .find(|sentinel| sentinel.state() == ConnectionState::Connected)
+            .map(ConnectionMonitor::sentinel)
+    }
+
+    fn write_status(&self) {
+        let statuses: Vec<_> = self
+            .routes
+            .iter()
+            .flat_map(|route| {
+                route
+                    .sentinels
+                    .iter()
+                    .map(|sentinel| sentinel.status(route.buffer.len()))
+            })
+            .collect();
+        self.status_file.write(self.mode.label(), &statuses);
+    }
+}
+
+/// Collects the current system report and sends it to the sentinel servers.
+/// Reports that could not be delivered earlier are replayed first, oldest
+/// first. While every sentinel is backing off the report is only buffered.
+pub async fn send_system_report(
+    collector: &Arc<Mutex<Collector>>,
+    delivery: &mut Delivery,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Collecting samples the CPU for a moment and runs container, systemd
+    // and package manager CLIs, which block
+    let report = {
+        let collector = collector.clone();
+        tokio::task::spawn_blocking(move || collector.lock().unwrap().collect()).await?
+    };
+    let carries_inventory = report.inventory.is_some();
+    let result = deliver_report(&mut delivery.routes, report).await;
+    if result.is_err() && carries_inventory {
+        collector.lock().unwrap().retry_inventory();
+    }
+
+    delivery.write_status();
+    result
+}
+
+/// Buffers the report on every route and delivers all routes at once
+async fn deliver_report(
+    routes: &mut [Route],
+    report: SystemReport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some((last, others)) = routes.split_last_mut() {
+        for route in others {
+            route.buffer.push(report.clone());
+        }
+        last.buffer.push(report);
+    }
+
+    let mut result = Ok(());
+    for outcome in join_all(routes.iter_mut().map(Route::deliver)).await {
+        if let Err(e) = outcome {
+            result = Err(e);
+        }
+    }
+    result
+}
+
+/// Connects to a sentinel with connect and request timeouts
+async fn connect(
+    server_addr: &str,
+) -> Result<SwarmReportServiceClient<Channel>, Box<dyn std::error::Error>> {
+    let channel = Endpoint::from_shared(server_addr.to_string())?
+        .connect_timeout(REQUEST_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .connect()
         .await?;
+    Ok(SwarmReportServiceClient::new(channel))
+}
+
+/// Delivers buffered reports in order over one connection, stopping at the
+/// first failure so nothing is skipped
+async fn flush_buffer(
+    buffer: &mut ReportBuffer,
+    server_addr: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server_addr).await?;
+    let backlog = buffer.len();
 
-    println!("Response from server: {:?}", response.into_inner());
+    while let Some(mut report) = buffer.oldest() {
+        report.sent_at = unix_millis();
+        let response = client
+            .send_system_report(tonic::Request::new(report))
+            .await?;
+        buffer.remove_oldest();
+
+        if buffer.is_empty() {
+            if backlog > 1 {
+                println!("Replayed {} buffered reports to {server_addr}", backlog - 1);
+            }
+            println!("Response from server: {:?}", response.into_inner());
+        }
+    }
     Ok(())
 }
 
-/// Retrieves aggregated swarm information from the sentinel server
-pub async fn get_swarm_report() -> Result<(), Box<dyn std::error::Error>> {
-    let server_addr =
-        std::env::var("SWARM_SENTINEL_ADDR").unwrap_or_else(|_| "http://gordon:50051".to_string());
-
-    let mut client = SwarmReportServiceClient::connect(server_addr).await?;
+/// Retrieves aggregated swarm information from a sentinel server and logs
+/// how many nodes it sees
+pub async fn get_swarm_report(server_addr: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect(server_addr)
+        .await?
+        .max_decoding_message_size(64 << 20);
     let response = client
         .get_swarm_report(tonic::Request::new(SwarmReportRequest {}))
-        .await?;
+        .await?
+        .into_inner();
 
-    println!("Swarm report: {:?}", response.into_inner());
+    match response.site.as_str() {
+        "" => println!("Swarm report: {} nodes", response.reports.len()),
+        site => println!(
+            "Swarm report: {} nodes (site {site})",
+            response.reports.len()
+        ),
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::time::Instant;
+
+    use tonic::{Request, Response, Status};
+
+    use super::*;
+    use crate::swarmreport::swarm_report_service_server::{
+        SwarmReportService, SwarmReportServiceServer,
+    };
+    use crate::swarmreport::{HaHeartbeat, ReportResponse, SwarmReportResponse};
+
+    /// A sentinel that records the hostnames it receives, answering after `delay`
+    #[derive(Clone, Default)]
+    struct FakeSentinel {
+        received: Arc<Mutex<Vec<String>>>,
+        delay: Duration,
+    }
+
+    #[tonic::async_trait]
+    impl SwarmReportService for FakeSentinel {
+        async fn send_system_report(
+            &self,
+            request: Request<SystemReport>,
+        ) -> Result<Response<ReportResponse>, Status> {
+            tokio::time::sleep(self.delay).await;
+            let hostname = request.into_inner().hostname;
+            self.received.lock().unwrap().push(hostname);
+            Ok(Response::new(ReportResponse {
+                success: true,
+                ..Default::default()
+            }))
+        }
+
+        async fn get_swarm_report(
+            &self,
+            _: Request<SwarmReportRequest>,
+        ) -> Result<Response<SwarmReportResponse>, Status> {
+            Ok(Response::new(SwarmReportResponse::default()))
+        }
+
+        async fn heartbeat(
+            &self,
+            request: Request<HaHeartbeat>,
+        ) -> Result<Response<HaHeartbeat>, Status> {
+            Ok(Response::new(request.into_inner()))
+        }
+    }
+
+    /// An address nothing listens on
+    fn unused_addr() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+    }
+
+    /// Serves `sentinel` on `addr` and waits until it accepts connections
+    async fn serve(sentinel: FakeSentinel, addr: SocketAddr) {
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(SwarmReportServiceServer::new(sentinel))
+                .serve(addr),
+        );
+        while tokio::net::TcpStream::connect(addr).await.is_err() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    fn report(hostname: &str) -> SystemReport {
+        SystemReport {
+            hostname: hostname.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn route(addrs: &[SocketAddr]) -> Route {
+        let addrs: Vec<String> = addrs.iter().map(|addr| format!("http://{addr}")).collect();
+        Route::new(ReportBuffer::from_env(None), &addrs)
+    }
+
+    #[tokio::test]
+    async fn failover_uses_the_first_reachable_sentinel_and_returns_to_the_primary() {
+        let (primary, secondary) = (FakeSentinel::default(), FakeSentinel::default());
+        let (primary_addr, secondary_addr) = (unused_addr(), unused_addr());
+        serve(secondary.clone(), secondary_addr).await;
+        let mut routes = [route(&[primary_addr, secondary_addr])];
+
+        deliver_report(&mut routes, report("first")).await.unwrap();
+        assert_eq!(routes[0].active, Some(1));
+        assert_eq!(*secondary.received.lock().unwrap(), ["first"]);
+
+        // While the primary backs off the secondary keeps taking reports
+        deliver_report(&mut routes, report("second")).await.unwrap();
+        assert_eq!(*secondary.received.lock().unwrap(), ["first", "second"]);
+
+        serve(primary.clone(), primary_addr).await;
+        while !routes[0].sentinels[0].ready() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        deliver_report(&mut routes, report("third")).await.unwrap();
+        assert_eq!(routes[0].active, Some(0));
+        assert_eq!(*primary.received.lock().unwrap(), ["third"]);
+        assert!(routes[0].buffer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failover_keeps_the_report_when_no_sentinel_answers() {
+        let mut routes = [route(&[unused_addr(), unused_addr()])];
+        assert!(deliver_report(&mut routes, report("kept")).await.is_err());
+        assert_eq!(routes[0].buffer.len(), 1);
+        assert_eq!(routes[0].active, None);
+
+        // Both are backing off now, so the next report is only buffered
+        deliver_report(&mut routes, report("also kept"))
+            .await
+            .unwrap();
+        assert_eq!(routes[0].buffer.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn fanout_delivers_to_every_sentinel_at_once() {
+        let slow = FakeSentinel {
+            delay: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let (first, second) = (
+            slow.clone(),
+            FakeSentinel {
+                received: Default::default(),
+                ..slow
+            },
+        );
+        let (first_addr, second_addr, dead_addr) = (unused_addr(), unused_addr(), unused_addr());
+        serve(first.clone(), first_addr).await;
+        serve(second.clone(), second_addr).await;
+        let mut routes = [
+            route(&[first_addr]),
+            route(&[second_addr]),
+            route(&[dead_addr]),
+        ];
+
+        let started = Instant::now();
+        assert!(
+            deliver_report(&mut routes, report("everyone"))
+                .await
+                .is_err()
+        );
+        assert!(started.elapsed() < Duration::from_millis(900));
+
+        assert_eq!(*first.received.lock().unwrap(), ["everyone"]);
+        assert_eq!(*second.received.lock().unwrap(), ["everyone"]);
+        // The unreachable sentinel keeps its own copy for later
+        assert_eq!(routes.map(|route| route.buffer.len()), [0, 0, 1]);
+    }
+}
@@ -0,0 +1,24 @@
+/// Environment-based configuration helpers for the reporter
+///
+/// All reporter settings are read from `SWARM_*` environment variables so the
+/// binary can be dropped onto a machine without a config file.
+use std::time::Duration;
+
+/// Reads a duration given in whole seconds, falling back to a default
+pub fn env_secs(name: &str, default_secs: u64) -> Duration {
+    let secs = std::env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(default_secs);
+    Duration::from_secs(secs)
+}
+
+/// Reads a comma-separated list, ignoring empty entries
+pub fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
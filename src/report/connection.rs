@@ -0,0 +1,251 @@
+/// Connection state and retry backoff for the reporter
+///
+/// After a failed delivery the next attempt is delayed exponentially, up to
+/// `SWARM_BACKOFF_MAX` seconds, with random jitter so a fleet doesn't
+/// reconnect to a restarting sentinel in lockstep. Reports keep being
+/// collected and buffered meanwhile. State changes are logged, and when
+/// `SWARM_STATUS_FILE` is set the state of every sentinel is written there
+/// as JSON.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::report::config::env_secs;
+
+/// Delay before the first retry, doubled on every further failure
+const BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Consecutive failures after which the sentinel counts as unreachable
+const DISCONNECTED_AFTER: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionState {
+    /// No delivery attempted yet
+    Connecting,
+    /// The last delivery succeeded
+    Connected,
+    /// Deliveries started failing; retrying
+    Degraded,
+    /// Several deliveries in a row failed
+    Disconnected,
+}
+
+/// Connection status of one sentinel, as written to the status file
+#[derive(Serialize)]
+pub struct ConnectionStatus<'a> {
+    state: ConnectionState,
+    sentinel: &'a str,
+    last_success: Option<u64>,
+    last_error: Option<&'a str>,
+    last_error_at: Option<u64>,
+    consecutive_failures: u32,
+    next_attempt_in_ms: u128,
+    buffered_reports: usize,
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// A random fraction in [0, 1)
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos()),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Delay before retrying after `failures` consecutive failures, given a
+/// random `jitter` in [0, 1). "Equal jitter": half fixed, half random.
+fn backoff_delay(failures: u32, max_delay: Duration, jitter: f64) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    let delay = (BASE_DELAY * 2u32.pow(exponent)).min(max_delay);
+    delay / 2 + delay.mul_f64(jitter / 2.0)
+}
+
+/// Tracks delivery outcomes for one sentinel and decides when to try again
+pub struct ConnectionMonitor {
+    sentinel: String,
+    state: ConnectionState,
+    consecutive_failures: u32,
+    next_attempt: Instant,
+    max_delay: Duration,
+    last_success: Option<u64>,
+    last_error: Option<(String, u64)>,
+}
+
+impl ConnectionMonitor {
+    pub fn from_env(sentinel: &str) -> Self {
+        Self {
+            sentinel: sentinel.to_string(),
+            state: ConnectionState::Connecting,
+            consecutive_failures: 0,
+            next_attempt: Instant::now(),
+            max_delay: env_secs("SWARM_BACKOFF_MAX", 60).max(BASE_DELAY),
+            last_success: None,
+            last_error: None,
+        }
+    }
+
+    pub fn sentinel(&self) -> &str {
+        &self.sentinel
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Whether the backoff delay has passed
+    pub fn ready(&self) -> bool {
+        Instant::now() >= self.next_attempt
+    }
+
+    pub fn succeeded(&mut self) {
+        self.consecutive_failures = 0;
+        self.last_success = Some(unix_secs());
+        self.next_attempt = Instant::now();
+        self.transition(ConnectionState::Connected, None);
+    }
+
+    pub fn failed(&mut self, error: &str) {
+        self.consecutive_failures += 1;
+        self.last_error = Some((error.to_string(), unix_secs()));
+
+        let delay = backoff_delay(self.consecutive_failures, self.max_delay, random_fraction());
+        self.next_attempt = Instant::now() + delay;
+
+        let state = if self.consecutive_failures >= DISCONNECTED_AFTER {
+            ConnectionState::Disconnected
+        } else {
+            ConnectionState::Degraded
+        };
+        self.transition(state, Some(error));
+    }
+
+    /// Logs and records a state change
+    fn transition(&mut self, state: ConnectionState, error: Option<&str>) {
+        if state == self.state {
+            return;
+        }
+        match error {
+            Some(error) => eprintln!(
+                "Connection to {}: {:?} -> {:?} ({error})",
+                self.sentinel, self.state, state
+            ),
+            None => println!(
+                "Connection to {}: {:?} -> {:?}",
+                self.sentinel, self.state, state
+            ),
+        }
+        self.state = state;
+    }
+
+    pub fn status(&self, buffered_reports: usize) -> ConnectionStatus<'_> {
+        ConnectionStatus {
+            state: self.state,
+            sentinel: &self.sentinel,
+            last_success: self.last_success,
+            last_error: self.last_error.as_ref().map(|(error, _)| error.as_str()),
+            last_error_at: self.last_error.as_ref().map(|(_, at)| *at),
+            consecutive_failures: self.consecutive_failures,
+            next_attempt_in_ms: self
+                .next_attempt
+                .saturating_duration_since(Instant::now())
+                .as_millis(),
+            buffered_reports,
+        }
+    }
+}
+
+/// Writes the reporter's status to `SWARM_STATUS_FILE`, replacing it atomically
+pub struct StatusFile {
+    path: Option<PathBuf>,
+}
+
+impl StatusFile {
+    pub fn from_env() -> Self {
+        Self {
+            path: std::env::var_os("SWARM_STATUS_FILE").map(PathBuf::from),
+        }
+    }
+
+    pub fn write(&self, mode: &str, sentinels: &[ConnectionStatus<'_>]) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let status = serde_json::json!({ "mode": mode, "sentinels": sentinels });
+        let Ok(json) = serde_json::to_vec_pretty(&status) else {
+            return;
+        };
+
+        let tmp = path.with_extension("tmp");
+        if let Err(e) = std::fs::write(&tmp, json).and_then(|()| std::fs::rename(&tmp, path)) {
+            eprintln!("Failed to write status file {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let fixed: Vec<_> = (1..=8)
+            .map(|failures| backoff_delay(failures, MAX, 0.0).as_millis())
+            .collect();
+        assert_eq!(
+            fixed,
+            [500, 1_000, 2_000, 4_000, 8_000, 16_000, 30_000, 30_000]
+        );
+        // The exponent is capped, so long outages don't overflow
+        assert_eq!(backoff_delay(1_000, MAX, 0.0), MAX / 2);
+    }
+
+    #[test]
+    fn jitter_stays_within_half_and_full_delay() {
+        for failures in 1..=10 {
+            let full = (BASE_DELAY * 2u32.pow(failures - 1)).min(MAX);
+            assert_eq!(backoff_delay(failures, MAX, 0.0), full / 2);
+            let highest = backoff_delay(failures, MAX, 0.999_999);
+            assert!(highest < full && highest > full.mul_f64(0.99));
+        }
+        for _ in 0..1_000 {
+            let jitter = random_fraction();
+            assert!((0.0..1.0).contains(&jitter));
+        }
+    }
+
+    #[test]
+    fn failures_degrade_then_disconnect_until_a_success() {
+        let mut monitor = ConnectionMonitor::from_env("http://sentinel:50051");
+        assert_eq!(monitor.state(), ConnectionState::Connecting);
+        assert!(monitor.ready());
+
+        monitor.failed("refused");
+        assert_eq!(monitor.state(), ConnectionState::Degraded);
+        assert!(!monitor.ready());
+        let waiting = monitor.status(0).next_attempt_in_ms;
+        assert!((400..=1_000).contains(&waiting));
+
+        monitor.failed("refused");
+        monitor.failed("refused");
+        assert_eq!(monitor.state(), ConnectionState::Disconnected);
+
+        monitor.succeeded();
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+        assert!(monitor.ready());
+        assert_eq!(monitor.status(0).consecutive_failures, 0);
+    }
+}
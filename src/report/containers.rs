@@ -0,0 +1,525 @@
+/// Container runtime collectors
+///
+/// Lists running containers from Docker, Podman and containerd so they can be
+/// reported as services. Runtimes are detected at startup or chosen with
+/// `SWARM_CONTAINER_RUNTIME` (a comma-separated list of docker, podman,
+/// containerd, or "auto").
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::report::config::env_list;
+use crate::report::updates::SharedImageUpdates;
+use crate::swarmreport::{Service, ServiceHealth, ServiceKind};
+
+/// A running container, independent of the runtime that reported it
+pub struct Container {
+    pub name: String,
+    pub image: String,
+    pub state: String,
+    pub health: ServiceHealth,
+    pub started_at: i64,
+    pub restart_count: u32,
+    pub ports: Vec<String>,
+    pub labels: HashMap<String, String>,
+}
+
+/// A container runtime we know how to query
+#[derive(Clone, Debug)]
+pub enum ContainerRuntime {
+    /// The Docker engine, queried through the `docker` CLI
+    Docker,
+    /// Podman, queried through its REST API socket
+    Podman { socket: PathBuf },
+    /// containerd, queried through `nerdctl` in the given namespace
+    Containerd { namespace: String },
+}
+
+/// A container as printed by `docker inspect`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerInspect {
+    name: String,
+    #[serde(default)]
+    restart_count: u32,
+    state: DockerState,
+    config: DockerConfig,
+    network_settings: DockerNetworkSettings,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerState {
+    status: String,
+    #[serde(default)]
+    started_at: String,
+    health: Option<DockerHealth>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerHealth {
+    status: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerConfig {
+    image: String,
+    labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerNetworkSettings {
+    ports: Option<HashMap<String, Option<Vec<DockerPortBinding>>>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerPortBinding {
+    host_port: String,
+}
+
+/// A container as returned by podman's `/libpod/containers/json`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PodmanContainer {
+    names: Vec<String>,
+    image: String,
+    state: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    started_at: i64,
+    #[serde(default)]
+    restarts: u32,
+    labels: Option<HashMap<String, String>>,
+    ports: Option<Vec<PodmanPort>>,
+}
+
+#[derive(Deserialize)]
+struct PodmanPort {
+    host_port: u16,
+    container_port: u16,
+    protocol: String,
+}
+
+/// A container as printed by `nerdctl ps --format '{{json .}}'`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NerdctlContainer {
+    names: String,
+    image: String,
+    status: String,
+    #[serde(default)]
+    ports: String,
+    #[serde(default)]
+    labels: String,
+}
+
+/// Reads the health suffix of a human status such as "Up 2 hours (healthy)"
+fn health_from_status(status: &str) -> ServiceHealth {
+    if status.contains("(unhealthy)") {
+        ServiceHealth::Unhealthy
+    } else if status.contains("(healthy)") {
+        ServiceHealth::Healthy
+    } else if status.contains("(health: starting)") || status.contains("(starting)") {
+        ServiceHealth::Starting
+    } else {
+        ServiceHealth::Unknown
+    }
+}
+
+/// Parses a "key=value,key=value" label string as printed by nerdctl
+fn parse_label_string(labels: &str) -> HashMap<String, String> {
+    labels
+        .split(',')
+        .filter_map(|label| label.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Parses an RFC 3339 timestamp into Unix seconds, 0 for unset or zero times
+fn parse_timestamp(timestamp: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.timestamp().max(0))
+        .unwrap_or(0)
+}
+
+/// Lists running Docker containers with the details only `docker inspect` has
+fn docker_containers() -> Vec<Container> {
+    let ids = command_output("docker", &["ps", "-q"]).unwrap_or_default();
+    let ids: Vec<&str> = ids.lines().collect();
+    if ids.is_empty() {
+        return vec![];
+    }
+
+    let mut args = vec!["inspect"];
+    args.extend(ids);
+
+    command_output("docker", &args)
+        .and_then(|out| serde_json::from_str::<Vec<DockerInspect>>(&out).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|c| {
+            let mut ports: Vec<String> = c
+                .network_settings
+                .ports
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|(container_port, bindings)| {
+                    let bindings = bindings.unwrap_or_default();
+                    if bindings.is_empty() {
+                        return vec![container_port];
+                    }
+                    bindings
+                        .into_iter()
+                        .map(|b| format!("{}->{container_port}", b.host_port))
+                        .collect()
+                })
+                .collect();
+            // IPv4 and IPv6 bindings of the same port print identically
+            ports.sort();
+            ports.dedup();
+
+            Container {
+                name: c.name.trim_start_matches('/').to_string(),
+                image: c.config.image,
+                state: c.state.status,
+                health: match c.state.health.as_ref().map(|h| h.status.as_str()) {
+                    Some("healthy") => ServiceHealth::Healthy,
+                    Some("unhealthy") => ServiceHealth::Unhealthy,
+                    Some("starting") => ServiceHealth::Starting,
+                    _ => ServiceHealth::Unknown,
+                },
+                started_at: parse_timestamp(&c.state.started_at),
+                restart_count: c.restart_count,
+                ports,
+                labels: c.config.labels.unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Runs a CLI command and returns its stdout if it succeeded
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    match Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => String::from_utf8(output.stdout).ok(),
+        _ => None,
+    }
+}
+
+/// Performs a GET request against an HTTP API served on a unix socket
+fn unix_socket_get(socket: &Path, path: &str) -> std::io::Result<Vec<u8>> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+    // HTTP/1.0 keeps the response unchunked and closes the connection when done
+    write!(stream, "GET {path} HTTP/1.0\r\nHost: localhost\r\n\r\n")?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| std::io::Error::other("malformed HTTP response"))?;
+    let status_line = String::from_utf8_lossy(&response[..header_end]);
+    if !status_line.starts_with("HTTP/1.1 200") && !status_line.starts_with("HTTP/1.0 200") {
+        return Err(std::io::Error::other(
+            status_line.lines().next().unwrap_or_default().to_string(),
+        ));
+    }
+
+    Ok(response[header_end + 4..].to_vec())
+}
+
+/// Finds the podman API socket, preferring an explicit `SWARM_PODMAN_SOCKET`
+fn podman_socket() -> Option<PathBuf> {
+    if let Ok(socket) = std::env::var("SWARM_PODMAN_SOCKET") {
+        return Some(PathBuf::from(socket));
+    }
+
+    let rootless = std::env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .map(|dir| PathBuf::from(dir).join("podman/podman.sock"));
+
+    rootless
+        .into_iter()
+        .chain([PathBuf::from("/run/podman/podman.sock")])
+        .find(|socket| socket.exists())
+}
+
+fn containerd_namespace() -> String {
+    std::env::var("SWARM_CONTAINERD_NAMESPACE").unwrap_or_else(|_| "default".to_string())
+}
+
+impl ContainerRuntime {
+    /// Selects runtimes from `SWARM_CONTAINER_RUNTIME`, detecting them when unset
+    pub fn from_env() -> Vec<Self> {
+        let configured = env_list("SWARM_CONTAINER_RUNTIME");
+        if configured.is_empty() || configured.iter().any(|r| r == "auto") {
+            return Self::detect();
+        }
+
+        configured
+            .iter()
+            .filter_map(|name| match name.as_str() {
+                "docker" => Some(ContainerRuntime::Docker),
+                "podman" => podman_socket().map(|socket| ContainerRuntime::Podman { socket }),
+                "containerd" | "nerdctl" => Some(ContainerRuntime::Containerd {
+                    namespace: containerd_namespace(),
+                }),
+                other => {
+                    eprintln!("Unknown container runtime '{other}', ignoring");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Detects every runtime that is installed and reachable on this machine
+    fn detect() -> Vec<Self> {
+        let mut runtimes = Vec::new();
+
+        let podman = podman_socket().filter(|socket| UnixStream::connect(socket).is_ok());
+
+        // podman-docker installs a `docker` shim; don't list podman twice
+        let docker_is_podman = command_output("docker", &["--version"])
+            .is_some_and(|version| version.to_lowercase().contains("podman"));
+        if !docker_is_podman && command_output("docker", &["info", "--format", "{{.ID}}"]).is_some()
+        {
+            runtimes.push(ContainerRuntime::Docker);
+        }
+
+        if let Some(socket) = podman {
+            runtimes.push(ContainerRuntime::Podman { socket });
+        }
+
+        let namespace = containerd_namespace();
+        if command_output("nerdctl", &["--namespace", &namespace, "ps", "-q"]).is_some() {
+            runtimes.push(ContainerRuntime::Containerd { namespace });
+        }
+
+        runtimes
+    }
+
+    /// Lists running containers, returning nothing if the runtime is unavailable
+    pub fn containers(&self) -> Vec<Container> {
+        match self {
+            ContainerRuntime::Docker => docker_containers(),
+            ContainerRuntime::Podman { socket } => {
+                unix_socket_get(socket, "/v4.0.0/libpod/containers/json")
+                    .ok()
+                    .and_then(|body| serde_json::from_slice::<Vec<PodmanContainer>>(&body).ok())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|c| Container {
+                        name: c.names.into_iter().next().unwrap_or_default(),
+                        image: c.image,
+                        state: c.state,
+                        health: health_from_status(&c.status),
+                        started_at: c.started_at,
+                        restart_count: c.restarts,
+                        ports: c
+                            .ports
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|p| {
+                                format!("{}->{}/{}", p.host_port, p.container_port, p.protocol)
+                            })
+                            .collect(),
+                        labels: c.labels.unwrap_or_default(),
+                    })
+                    .collect()
+            }
+            ContainerRuntime::Containerd { namespace } => command_output(
+                "nerdctl",
+                &["--namespace", namespace, "ps", "--format", "{{json .}}"],
+            )
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str::<NerdctlContainer>(line).ok())
+            .map(|c| Container {
+                name: c.names,
+                image: c.image,
+                // nerdctl only reports a human status such as "Up 2 hours"
+                state: if c.status.starts_with("Up") {
+                    "running".to_string()
+                } else {
+                    c.status.to_lowercase()
+                },
+                health: health_from_status(&c.status),
+                started_at: 0,
+                restart_count: 0,
+                ports: c
+                    .ports
+                    .split(", ")
+                    .filter(|port| !port.is_empty())
+                    .map(|port| port.rsplit_once(':').map_or(port, |(_, p)| p).to_string())
+                    .collect(),
+                labels: parse_label_string(&c.labels),
+            })
+            .collect(),
+        }
+    }
+
+    /// Returns the registry digests the runtime has recorded for a local image
+    pub fn image_digests(&self, image: &str) -> Vec<String> {
+        let repo_digests: Vec<String> = match self {
+            ContainerRuntime::Docker => command_output(
+                "docker",
+                &[
+                    "image",
+                    "inspect",
+                    "--format",
+                    "{{json .RepoDigests}}",
+                    image,
+                ],
+            )
+            .and_then(|out| serde_json::from_str(&out).ok())
+            .unwrap_or_default(),
+            ContainerRuntime::Podman { socket } => {
+                #[derive(Deserialize)]
+                #[serde(rename_all = "PascalCase")]
+                struct PodmanImage {
+                    #[serde(default)]
+                    repo_digests: Vec<String>,
+                }
+
+                unix_socket_get(socket, &format!("/v4.0.0/libpod/images/{image}/json"))
+                    .ok()
+                    .and_then(|body| serde_json::from_slice::<PodmanImage>(&body).ok())
+                    .map(|i| i.repo_digests)
+                    .unwrap_or_default()
+            }
+            ContainerRuntime::Containerd { namespace } => command_output(
+                "nerdctl",
+                &[
+                    "--namespace",
+                    namespace,
+                    "image",
+                    "inspect",
+                    "--format",
+                    "{{json .RepoDigests}}",
+                    image,
+                ],
+            )
+            .and_then(|out| serde_json::from_str(&out).ok())
+            .unwrap_or_default(),
+        };
+
+        repo_digests
+            .into_iter()
+            .filter_map(|digest| digest.split_once('@').map(|(_, d)| d.to_string()))
+            .collect()
+    }
+}
+
+/// Picks a container's version from its OCI label, falling back to the image tag
+fn image_version(container: &Container) -> String {
+    if let Some(version) = container.labels.get("org.opencontainers.image.version") {
+        return version.clone();
+    }
+
+    // A digest-pinned image has no tag unless one is given before the digest,
+    // and the digest itself contains a ':'
+    let (reference, pinned) = match container.image.split_once('@') {
+        Some((reference, _)) => (reference, true),
+        None => (container.image.as_str(), false),
+    };
+    let without_registry = reference.rsplit('/').next().unwrap_or_default();
+    match without_registry.split_once(':') {
+        Some((_, tag)) => tag.to_string(),
+        None if pinned => String::new(),
+        None => "latest".to_string(),
+    }
+}
+
+/// Gets running containers from all runtimes as services
+pub fn get_container_services(
+    runtimes: &[ContainerRuntime],
+    image_updates: &SharedImageUpdates,
+) -> Vec<Service> {
+    // Query the runtimes before locking, so the update checker isn't held up
+    // while they answer
+    let containers: Vec<Container> = runtimes
+        .iter()
+        .flat_map(|runtime| runtime.containers())
+        .collect();
+    let image_updates = image_updates.lock().unwrap();
+
+    containers
+        .into_iter()
+        .map(|container| Service {
+            needs_update: image_updates
+                .get(&container.image)
+                .copied()
+                .unwrap_or(false),
+            version: image_version(&container),
+            name: container.name,
+            status: container.state,
+            image: container.image,
+            kind: ServiceKind::Container.into(),
+            health: container.health.into(),
+            started_at: container.started_at,
+            since: container.started_at,
+            restart_count: container.restart_count,
+            ports: container.ports,
+            labels: container.labels,
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(image: &str) -> Container {
+        Container {
+            name: "app".to_string(),
+            image: image.to_string(),
+            state: "running".to_string(),
+            health: ServiceHealth::Unknown,
+            started_at: 0,
+            restart_count: 0,
+            ports: Vec::new(),
+            labels: HashMap::new(),
+        }
+    }
+
+    fn version_of(image: &str) -> String {
+        image_version(&container(image))
+    }
+
+    #[test]
+    fn image_version_is_the_tag() {
+        assert_eq!(version_of("nginx:1.27"), "1.27");
+        assert_eq!(version_of("nginx"), "latest");
+        assert_eq!(version_of("registry:5000/team/app:2.1"), "2.1");
+        assert_eq!(version_of("registry:5000/team/app"), "latest");
+    }
+
+    #[test]
+    fn digest_pinned_images_have_no_version() {
+        assert_eq!(version_of("nginx@sha256:0123abcd"), "");
+        assert_eq!(version_of("registry:5000/team/app@sha256:0123abcd"), "");
+        assert_eq!(version_of("nginx:1.27@sha256:0123abcd"), "1.27");
+    }
+
+    #[test]
+    fn image_version_prefers_the_oci_label() {
+        let mut container = container("team/app@sha256:0123abcd");
+        container.labels.insert(
+            "org.opencontainers.image.version".to_string(),
+            "3.0.1".to_string(),
+        );
+        assert_eq!(image_version(&container), "3.0.1");
+    }
+}
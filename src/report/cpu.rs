@@ -0,0 +1,92 @@
+/// CPU load collection
+///
+/// Adds load averages, uptime and per-core utilization to the global CPU
+/// figure. On Linux, iowait and steal are derived from `/proc/stat` deltas
+/// between reports, which separates a node starved by its hypervisor from
+/// one waiting on disks.
+use sysinfo::System;
+
+use crate::swarmreport::CpuInfo;
+
+/// Aggregate jiffies from the `cpu` line of `/proc/stat`
+#[derive(Clone, Copy)]
+struct CpuTimes {
+    total: u64,
+    iowait: u64,
+    steal: u64,
+}
+
+fn read_cpu_times() -> Option<CpuTimes> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+
+    // user nice system idle iowait irq softirq steal guest guest_nice; guest
+    // time is already counted in user and nice, so only the first eight add up
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    if fields.len() < 8 {
+        return None;
+    }
+
+    Some(CpuTimes {
+        total: fields.iter().sum(),
+        iowait: fields[4],
+        steal: fields[7],
+    })
+}
+
+/// Tracks `/proc/stat` counters across reports
+pub struct CpuCollector {
+    last_times: Option<CpuTimes>,
+}
+
+impl CpuCollector {
+    pub fn new() -> Self {
+        Self {
+            last_times: read_cpu_times(),
+        }
+    }
+
+    /// Builds the CPU breakdown from a refreshed `System`
+    pub fn collect(&mut self, sys: &System) -> CpuInfo {
+        let load = System::load_average();
+        let times = read_cpu_times();
+
+        let (iowait_percent, steal_percent) = match (self.last_times, times) {
+            (Some(last), Some(now)) if now.total > last.total => {
+                let elapsed = (now.total - last.total) as f64;
+                (
+                    now.iowait.saturating_sub(last.iowait) as f64 / elapsed * 100.0,
+                    now.steal.saturating_sub(last.steal) as f64 / elapsed * 100.0,
+                )
+            }
+            _ => (0.0, 0.0),
+        };
+        self.last_times = times;
+
+        CpuInfo {
+            load_1: load.one,
+            load_5: load.five,
+            load_15: load.fifteen,
+            boot_time: System::boot_time(),
+            uptime_secs: System::uptime(),
+            physical_cores: System::physical_core_count().unwrap_or(0) as u32,
+            core_usage: sys
+                .cpus()
+                .iter()
+                .map(|cpu| cpu.cpu_usage() as f64)
+                .collect(),
+            iowait_percent,
+            steal_percent,
+            brand: sys
+                .cpus()
+                .first()
+                .map(|cpu| cpu.brand().trim().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
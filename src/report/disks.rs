@@ -0,0 +1,181 @@
+/// Per-mount disk usage collection
+///
+/// Reports every mounted filesystem separately instead of one summed total.
+/// Pseudo and container filesystems are filtered out, and bind mounts of the
+/// same device are reported once under their shortest mount point.
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use sysinfo::Disks;
+
+use crate::report::config::env_list;
+use crate::swarmreport::DiskInfo;
+
+/// Filesystem types skipped unless `SWARM_DISK_EXCLUDE_FS` says otherwise
+const DEFAULT_EXCLUDED_FS: &[&str] = &[
+    "tmpfs", "devtmpfs", "overlay", "squashfs", "efivarfs", "ramfs", "nsfs",
+];
+
+/// Mount point prefixes skipped unless `SWARM_DISK_EXCLUDE_MOUNTS` says otherwise
+const DEFAULT_EXCLUDED_MOUNTS: &[&str] = &["/snap", "/var/lib/docker"];
+
+/// Which mounts to report, read from the environment
+pub struct DiskFilter {
+    /// When non-empty, only these mount points are reported
+    include_mounts: Vec<String>,
+    exclude_fs: Vec<String>,
+    exclude_mounts: Vec<String>,
+}
+
+/// Reads an env list, falling back to defaults only when the variable is unset
+fn env_list_or(name: &str, defaults: &[&str]) -> Vec<String> {
+    if std::env::var_os(name).is_some() {
+        env_list(name)
+    } else {
+        defaults.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+impl DiskFilter {
+    pub fn from_env() -> Self {
+        Self {
+            include_mounts: env_list("SWARM_DISK_INCLUDE_MOUNTS"),
+            exclude_fs: env_list_or("SWARM_DISK_EXCLUDE_FS", DEFAULT_EXCLUDED_FS),
+            exclude_mounts: env_list_or("SWARM_DISK_EXCLUDE_MOUNTS", DEFAULT_EXCLUDED_MOUNTS),
+        }
+    }
+
+    fn allows(&self, mount_point: &str, file_system: &str) -> bool {
+        if !self.include_mounts.is_empty() {
+            return self.include_mounts.iter().any(|m| m == mount_point);
+        }
+
+        let under = |prefix: &String| {
+            mount_point == prefix || mount_point.starts_with(&format!("{prefix}/"))
+        };
+        !self.exclude_fs.iter().any(|fs| fs == file_system)
+            && !self.exclude_mounts.iter().any(under)
+    }
+}
+
+/// Usage figures sysinfo doesn't expose, read via statvfs
+struct FsStats {
+    /// Used bytes as `df` counts them, excluding blocks reserved for root
+    used_bytes: u64,
+    total_inodes: u64,
+    used_inodes: u64,
+}
+
+fn fs_stats(mount_point: &Path) -> Option<FsStats> {
+    let path = CString::new(mount_point.as_os_str().as_bytes()).ok()?;
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is writable
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let total_inodes = stat.f_files as u64;
+    Some(FsStats {
+        used_bytes: (stat.f_blocks as u64).saturating_sub(stat.f_bfree as u64)
+            * stat.f_frsize as u64,
+        total_inodes,
+        used_inodes: total_inodes.saturating_sub(stat.f_ffree as u64),
+    })
+}
+
+/// Collects usage for every mounted filesystem that passes the filter
+pub fn get_disks(filter: &DiskFilter) -> Vec<DiskInfo> {
+    let disks = Disks::new_with_refreshed_list();
+    let mut reported: Vec<DiskInfo> = Vec::new();
+
+    for disk in disks.list() {
+        let mount_point = disk.mount_point().to_string_lossy().to_string();
+        let file_system = disk.file_system().to_string_lossy().to_string();
+        if !filter.allows(&mount_point, &file_system) {
+            continue;
+        }
+
+        let device = disk.name().to_string_lossy().to_string();
+
+        // Bind mounts show the same device again; keep the shortest mount point
+        if let Some(existing) = reported.iter_mut().find(|d| d.device == device) {
+            if mount_point.len() < existing.mount_point.len() {
+                existing.mount_point = mount_point;
+            }
+            continue;
+        }
+
+        let total_bytes = disk.total_space();
+        let available_bytes = disk.available_space();
+        let stats = fs_stats(disk.mount_point());
+
+        reported.push(DiskInfo {
+            mount_point,
+            device,
+            file_system,
+            total_bytes,
+            used_bytes: stats
+                .as_ref()
+                .map_or(total_bytes.saturating_sub(available_bytes), |s| {
+                    s.used_bytes
+                }),
+            available_bytes,
+            total_inodes: stats.as_ref().map_or(0, |s| s.total_inodes),
+            used_inodes: stats.as_ref().map_or(0, |s| s.used_inodes),
+            removable: disk.is_removable(),
+        });
+    }
+
+    reported.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    reported
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> DiskFilter {
+        let strings = |list: &[&str]| list.iter().map(|s| s.to_string()).collect();
+        DiskFilter {
+            include_mounts: Vec::new(),
+            exclude_fs: strings(DEFAULT_EXCLUDED_FS),
+            exclude_mounts: strings(DEFAULT_EXCLUDED_MOUNTS),
+        }
+    }
+
+    #[test]
+    fn real_filesystems_are_reported() {
+        let filter = defaults();
+        assert!(filter.allows("/", "ext4"));
+        assert!(filter.allows("/boot/efi", "vfat"));
+        assert!(filter.allows("/mnt/data", "xfs"));
+    }
+
+    #[test]
+    fn pseudo_and_container_filesystems_are_skipped() {
+        let filter = defaults();
+        for file_system in ["tmpfs", "devtmpfs", "overlay", "squashfs", "efivarfs"] {
+            assert!(!filter.allows("/run", file_system), "{file_system}");
+        }
+        assert!(!filter.allows("/snap/core22/1380", "ext4"));
+        assert!(!filter.allows("/var/lib/docker", "ext4"));
+        assert!(!filter.allows("/var/lib/docker/volumes/data", "ext4"));
+        // Only whole path components match an excluded prefix
+        assert!(filter.allows("/snapshots", "btrfs"));
+        assert!(filter.allows("/var/lib/docker-backup", "ext4"));
+    }
+
+    #[test]
+    fn included_mounts_replace_the_exclusions() {
+        let filter = DiskFilter {
+            include_mounts: vec!["/".to_string(), "/srv".to_string()],
+            ..defaults()
+        };
+        assert!(filter.allows("/", "ext4"));
+        assert!(filter.allows("/srv", "tmpfs"));
+        assert!(!filter.allows("/srv/www", "ext4"));
+        assert!(!filter.allows("/home", "ext4"));
+    }
+}
@@ -0,0 +1,146 @@
+/// Host inventory collection
+///
+/// Gathers facts that rarely change (OS, kernel, hardware, virtualization).
+/// They are attached to a report only every `SWARM_INVENTORY_INTERVAL`
+/// seconds; the sentinel keeps the latest inventory per node.
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use sysinfo::System;
+
+use crate::report::config::env_secs;
+use crate::swarmreport::HostInventory;
+
+/// Runs `systemd-detect-virt` with the given flag, None when it's unavailable
+fn detect_virt(flag: &str) -> Option<String> {
+    let output = Command::new("systemd-detect-virt")
+        .arg(flag)
+        .output()
+        .ok()?;
+    // Exits non-zero and prints "none" when nothing is detected
+    let detected = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(if detected == "none" {
+        String::new()
+    } else {
+        detected
+    })
+}
+
+fn read_trimmed(path: &str) -> String {
+    std::fs::read_to_string(path)
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Detects the hypervisor from DMI data when systemd isn't around
+fn guess_virtualization() -> String {
+    let vendor = format!(
+        "{} {}",
+        read_trimmed("/sys/class/dmi/id/sys_vendor"),
+        read_trimmed("/sys/class/dmi/id/product_name")
+    )
+    .to_lowercase();
+
+    let known = [
+        ("qemu", "qemu"),
+        ("kvm", "kvm"),
+        ("vmware", "vmware"),
+        ("virtualbox", "oracle"),
+        ("microsoft corporation", "microsoft"),
+        ("xen", "xen"),
+        ("amazon ec2", "amazon"),
+        ("google compute engine", "google"),
+        ("parallels", "parallels"),
+    ];
+    if let Some((_, name)) = known.iter().find(|(pattern, _)| vendor.contains(pattern)) {
+        return name.to_string();
+    }
+
+    let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    if cpuinfo
+        .lines()
+        .any(|line| line.starts_with("flags") && line.split_whitespace().any(|f| f == "hypervisor"))
+    {
+        return "unknown".to_string();
+    }
+
+    String::new()
+}
+
+/// Detects the container the reporter runs in when systemd isn't around
+fn guess_container() -> String {
+    if Path::new("/.dockerenv").exists() {
+        return "docker".to_string();
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return "podman".to_string();
+    }
+
+    // PID 1's environment names the container manager for LXC and systemd-nspawn
+    let environ = std::fs::read("/proc/1/environ").unwrap_or_default();
+    String::from_utf8_lossy(&environ)
+        .split('\0')
+        .find_map(|var| var.strip_prefix("container="))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Collects the host inventory
+pub fn collect_inventory() -> HostInventory {
+    let mut sys = System::new();
+    sys.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+    sys.refresh_memory();
+
+    HostInventory {
+        os_name: System::name().unwrap_or_default(),
+        os_version: System::os_version().unwrap_or_default(),
+        kernel_version: System::kernel_version().unwrap_or_default(),
+        architecture: System::cpu_arch(),
+        cpu_model: sys
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string())
+            .unwrap_or_default(),
+        logical_cores: sys.cpus().len() as u32,
+        physical_cores: System::physical_core_count().unwrap_or(0) as u32,
+        total_memory_bytes: sys.total_memory(),
+        virtualization: detect_virt("--vm").unwrap_or_else(guess_virtualization),
+        container: detect_virt("--container").unwrap_or_else(guess_container),
+        reporter_version: env!("CARGO_PKG_VERSION").to_string(),
+        distribution_id: System::distribution_id(),
+    }
+}
+
+/// Decides when the next report carries the inventory
+pub struct InventorySchedule {
+    interval: Duration,
+    last_sent: Option<Instant>,
+}
+
+impl InventorySchedule {
+    pub fn from_env() -> Self {
+        Self {
+            interval: env_secs("SWARM_INVENTORY_INTERVAL", 600),
+            last_sent: None,
+        }
+    }
+
+    /// Returns the inventory when it is due, marking it as sent
+    pub fn next(&mut self) -> Option<HostInventory> {
+        let due = self
+            .last_sent
+            .is_none_or(|sent| sent.elapsed() >= self.interval);
+        if !due {
+            return None;
+        }
+
+        self.last_sent = Some(Instant::now());
+        Some(collect_inventory())
+    }
+
+    /// Makes the next report carry the inventory again, e.g. after a failed send
+    pub fn retry(&mut self) {
+        self.last_sent = None;
+    }
+}
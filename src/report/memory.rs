@@ -0,0 +1,66 @@
+/// Memory collection
+///
+/// Reports exact byte counts instead of a preformatted string, adding the
+/// page cache and buffers from `/proc/meminfo` and pressure stall
+/// information from `/proc/pressure/memory` where the kernel provides them.
+use sysinfo::System;
+
+use crate::swarmreport::{MemoryInfo, MemoryPressure};
+
+/// Reads a `/proc/meminfo` field, converted from kB to bytes
+fn meminfo_bytes(meminfo: &str, field: &str) -> u64 {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+        .map_or(0, |kb| kb * 1024)
+}
+
+/// Parses the averages of a PSI line such as
+/// "some avg10=0.00 avg60=0.12 avg300=0.05 total=1234"
+fn parse_pressure_line(line: &str) -> (f64, f64, f64) {
+    let average = |key: &str| {
+        line.split_whitespace()
+            .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0.0)
+    };
+    (average("avg10"), average("avg60"), average("avg300"))
+}
+
+fn get_pressure() -> Option<MemoryPressure> {
+    let pressure = std::fs::read_to_string("/proc/pressure/memory").ok()?;
+    let mut memory_pressure = MemoryPressure::default();
+
+    for line in pressure.lines() {
+        let (avg10, avg60, avg300) = parse_pressure_line(line);
+        if line.starts_with("some ") {
+            memory_pressure.some_avg10 = avg10;
+            memory_pressure.some_avg60 = avg60;
+            memory_pressure.some_avg300 = avg300;
+        } else if line.starts_with("full ") {
+            memory_pressure.full_avg10 = avg10;
+            memory_pressure.full_avg60 = avg60;
+            memory_pressure.full_avg300 = avg300;
+        }
+    }
+
+    Some(memory_pressure)
+}
+
+/// Builds the memory breakdown from a `System` with refreshed memory
+pub fn get_memory(sys: &System) -> MemoryInfo {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+
+    MemoryInfo {
+        total_bytes: sys.total_memory(),
+        used_bytes: sys.used_memory(),
+        available_bytes: sys.available_memory(),
+        free_bytes: sys.free_memory(),
+        cached_bytes: meminfo_bytes(&meminfo, "Cached"),
+        buffers_bytes: meminfo_bytes(&meminfo, "Buffers"),
+        swap_total_bytes: sys.total_swap(),
+        swap_used_bytes: sys.used_swap(),
+        pressure: get_pressure(),
+    }
+}
@@ -1,4 +1,23 @@
+pub mod buffer;
+pub mod certs;
 pub mod client;
+pub mod config;
+pub mod connection;
+pub mod containers;
+pub mod cpu;
+pub mod disks;
+pub mod inventory;
+pub mod memory;
+pub mod network;
+pub mod packages;
+pub mod plugins;
+pub mod probes;
+pub mod processes;
+pub mod sensors;
+pub mod swarm;
 pub mod system;
+pub mod systemd;
+pub mod tailscale;
+pub mod updates;
 
 pub use client::*;
@@ -0,0 +1,259 @@
+/// Network interface collection
+///
+/// Keeps sysinfo's interface counters between reports so traffic can be
+/// reported as rates. Interfaces matching `SWARM_NET_EXCLUDE` (names, or
+/// prefixes ending in `*`) are skipped.
+use std::net::IpAddr;
+use std::time::Instant;
+
+use sysinfo::Networks;
+
+use crate::report::config::env_list;
+use crate::swarmreport::{AddressFamily, AddressScope, IpAddress, NetworkInterface};
+
+/// Interfaces skipped unless `SWARM_NET_EXCLUDE` says otherwise
+const DEFAULT_EXCLUDED: &str = "lo,veth*";
+
+/// Reads a counter from `/sys/class/net/<interface>/statistics`, 0 elsewhere
+fn sys_statistic(interface: &str, name: &str) -> u64 {
+    std::fs::read_to_string(format!("/sys/class/net/{interface}/statistics/{name}"))
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Whether the kernel reports the interface as operationally up
+fn is_up(interface: &str, has_addresses: bool) -> bool {
+    match std::fs::read_to_string(format!("/sys/class/net/{interface}/operstate")) {
+        // Tunnels such as tailscale0 report "unknown" while passing traffic
+        Ok(state) => matches!(state.trim(), "up" | "unknown"),
+        Err(_) => has_addresses,
+    }
+}
+
+/// Classifies an address by where it can be reached from
+fn scope(ip: IpAddr, interface: &str) -> AddressScope {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            if interface.starts_with("tailscale") || (a == 100 && (64..128).contains(&b)) {
+                AddressScope::Tailscale
+            } else if v4.is_link_local() {
+                AddressScope::LinkLocal
+            } else if v4.is_private() {
+                AddressScope::Lan
+            } else {
+                AddressScope::Public
+            }
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            if interface.starts_with("tailscale") || segments[..3] == [0xfd7a, 0x115c, 0xa1e0] {
+                AddressScope::Tailscale
+            } else if segments[0] & 0xffc0 == 0xfe80 {
+                AddressScope::LinkLocal
+            } else if segments[0] & 0xfe00 == 0xfc00 {
+                AddressScope::Lan
+            } else {
+                AddressScope::Public
+            }
+        }
+    }
+}
+
+/// Lists the addresses of the given interfaces with their family and scope,
+/// skipping loopback addresses
+pub fn ip_addresses(interfaces: &[NetworkInterface]) -> Vec<IpAddress> {
+    interfaces
+        .iter()
+        .flat_map(|interface| {
+            interface.addresses.iter().filter_map(|cidr| {
+                let (address, prefix) = cidr.split_once('/').unwrap_or((cidr, "0"));
+                let ip: IpAddr = address.parse().ok()?;
+                if ip.is_loopback() {
+                    return None;
+                }
+                Some(IpAddress {
+                    address: ip.to_string(),
+                    prefix_length: prefix.parse().unwrap_or(0),
+                    interface: interface.name.clone(),
+                    family: match ip {
+                        IpAddr::V4(_) => AddressFamily::Ipv4,
+                        IpAddr::V6(_) => AddressFamily::Ipv6,
+                    }
+                    .into(),
+                    scope: scope(ip, &interface.name).into(),
+                })
+            })
+        })
+        .collect()
+}
+
+/// Tracks interface counters across reports
+pub struct NetworkCollector {
+    networks: Networks,
+    last_refresh: Instant,
+    excluded: Vec<String>,
+}
+
+impl NetworkCollector {
+    pub fn new() -> Self {
+        let excluded = if std::env::var_os("SWARM_NET_EXCLUDE").is_some() {
+            env_list("SWARM_NET_EXCLUDE")
+        } else {
+            DEFAULT_EXCLUDED.split(',').map(str::to_string).collect()
+        };
+
+        Self {
+            networks: Networks::new_with_refreshed_list(),
+            last_refresh: Instant::now(),
+            excluded,
+        }
+    }
+
+    fn is_excluded(&self, interface: &str) -> bool {
+        self.excluded
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => interface.starts_with(prefix),
+                None => interface == pattern,
+            })
+    }
+
+    /// Refreshes counters and returns per-interface rates since the last call
+    pub fn collect(&mut self) -> Vec<NetworkInterface> {
+        self.networks.refresh(true);
+        let elapsed = self.last_refresh.elapsed().as_secs_f64().max(0.001);
+        self.last_refresh = Instant::now();
+
+        let mut interfaces: Vec<NetworkInterface> = self
+            .networks
+            .list()
+            .iter()
+            .filter(|(name, _)| !self.is_excluded(name))
+            .map(|(name, data)| {
+                let addresses: Vec<String> = data
+                    .ip_networks()
+                    .iter()
+                    .map(|network| network.to_string())
+                    .collect();
+
+                NetworkInterface {
+                    name: name.clone(),
+                    up: is_up(name, !addresses.is_empty()),
+                    addresses,
+                    mac_address: data.mac_address().to_string(),
+                    mtu: data.mtu(),
+                    rx_bytes_per_sec: data.received() as f64 / elapsed,
+                    tx_bytes_per_sec: data.transmitted() as f64 / elapsed,
+                    rx_packets_per_sec: data.packets_received() as f64 / elapsed,
+                    tx_packets_per_sec: data.packets_transmitted() as f64 / elapsed,
+                    rx_errors: data.total_errors_on_received(),
+                    tx_errors: data.total_errors_on_transmitted(),
+                    rx_dropped: sys_statistic(name, "rx_dropped"),
+                    tx_dropped: sys_statistic(name, "tx_dropped"),
+                }
+            })
+            .collect();
+
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        interfaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope_of(address: &str, interface: &str) -> AddressScope {
+        scope(address.parse().unwrap(), interface)
+    }
+
+    #[test]
+    fn classifies_ipv4_scopes() {
+        assert_eq!(scope_of("169.254.10.1", "eth0"), AddressScope::LinkLocal);
+        for private in ["10.0.0.5", "172.16.4.1", "172.31.255.254", "192.168.1.100"] {
+            assert_eq!(scope_of(private, "eth0"), AddressScope::Lan, "{private}");
+        }
+        assert_eq!(scope_of("172.32.0.1", "eth0"), AddressScope::Public);
+        assert_eq!(scope_of("203.0.113.7", "eth0"), AddressScope::Public);
+        // The carrier-grade NAT range Tailscale uses, on any interface
+        assert_eq!(
+            scope_of("100.64.0.10", "tailscale0"),
+            AddressScope::Tailscale
+        );
+        assert_eq!(scope_of("100.127.255.1", "wg0"), AddressScope::Tailscale);
+        assert_eq!(scope_of("100.128.0.1", "eth0"), AddressScope::Public);
+    }
+
+    #[test]
+    fn classifies_ipv6_scopes() {
+        assert_eq!(
+            scope_of("fe80::1ff:fe23:4567:890a", "eth0"),
+            AddressScope::LinkLocal
+        );
+        assert_eq!(scope_of("fd12:3456:789a::1", "eth0"), AddressScope::Lan);
+        assert_eq!(scope_of("2001:db8::100", "eth0"), AddressScope::Public);
+        assert_eq!(
+            scope_of("fd7a:115c:a1e0::a", "eth0"),
+            AddressScope::Tailscale
+        );
+        assert_eq!(
+            scope_of("2001:db8::1", "tailscale0"),
+            AddressScope::Tailscale
+        );
+    }
+
+    #[test]
+    fn lists_addresses_without_loopback() {
+        let interfaces = [
+            NetworkInterface {
+                name: "lo".to_string(),
+                addresses: vec!["127.0.0.1/8".to_string(), "::1/128".to_string()],
+                ..Default::default()
+            },
+            NetworkInterface {
+                name: "eth0".to_string(),
+                addresses: vec![
+                    "192.168.1.100/24".to_string(),
+                    "2001:db8::100/64".to_string(),
+                    "not-an-address/24".to_string(),
+                ],
+                ..Default::default()
+            },
+        ];
+
+        let addresses = ip_addresses(&interfaces);
+        let listed: Vec<_> = addresses
+            .iter()
+            .map(|address| {
+                (
+                    address.address.as_str(),
+                    address.prefix_length,
+                    address.interface.as_str(),
+                    address.family(),
+                    address.scope(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (
+                    "192.168.1.100",
+                    24,
+                    "eth0",
+                    AddressFamily::Ipv4,
+                    AddressScope::Lan
+                ),
+                (
+                    "2001:db8::100",
+                    64,
+                    "eth0",
+                    AddressFamily::Ipv6,
+                    AddressScope::Public
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+/// OS package update detection
+///
+/// Counts pending package updates with the system's package manager (apt,
+/// dnf, pacman or apk) on a slow schedule, and checks whether a reboot is
+/// needed to finish earlier upgrades. Package lists are not refreshed here;
+/// the counts reflect whatever the system's own update timer last fetched.
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::report::config::env_secs;
+use crate::swarmreport::PackageUpdates;
+
+/// Latest check result, None until the first check finishes
+pub type SharedPackageUpdates = Arc<Mutex<Option<PackageUpdates>>>;
+
+/// Package names sent per report at most
+const MAX_PACKAGE_NAMES: usize = 50;
+
+/// Supported package managers, in detection order
+#[derive(Clone, Copy, Debug)]
+enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Apk,
+}
+
+impl PackageManager {
+    fn detect() -> Option<Self> {
+        let exists = |binary: &str| {
+            ["/usr/bin", "/bin", "/usr/sbin", "/sbin"]
+                .iter()
+                .any(|dir| Path::new(dir).join(binary).exists())
+        };
+
+        if exists("apt") {
+            Some(PackageManager::Apt)
+        } else if exists("dnf") {
+            Some(PackageManager::Dnf)
+        } else if exists("pacman") {
+            Some(PackageManager::Pacman)
+        } else if exists("apk") {
+            Some(PackageManager::Apk)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Apk => "apk",
+        }
+    }
+
+    /// Lists pending packages as (name, is security update)
+    fn pending(self) -> Vec<(String, bool)> {
+        match self {
+            PackageManager::Apt => apt_pending(),
+            PackageManager::Dnf => dnf_pending(),
+            PackageManager::Pacman => first_words(&["checkupdates"]),
+            PackageManager::Apk => apk_pending(),
+        }
+    }
+}
+
+/// Runs a command and returns its stdout, regardless of the exit status since
+/// several of these tools signal "updates available" with a non-zero code
+fn stdout_of(args: &[&str]) -> String {
+    Command::new(args[0])
+        .args(&args[1..])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+        .unwrap_or_default()
+}
+
+/// Takes the first word of every non-empty output line as a package name
+fn first_words(args: &[&str]) -> Vec<(String, bool)> {
+    parse_first_words(&stdout_of(args))
+}
+
+fn parse_first_words(output: &str) -> Vec<(String, bool)> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| (name.to_string(), false))
+        .collect()
+}
+
+/// Parses `apt list --upgradable`, e.g.
+/// "openssl/jammy-security 3.0.2-0ubuntu1.15 amd64 [upgradable from: 3.0.2-0ubuntu1.14]"
+fn apt_pending() -> Vec<(String, bool)> {
+    parse_apt(&stdout_of(&["apt", "list", "--upgradable"]))
+}
+
+fn parse_apt(output: &str) -> Vec<(String, bool)> {
+    output
+        .lines()
+        .filter(|line| line.contains("[upgradable from"))
+        .filter_map(|line| {
+            let (name, origins) = line.split_whitespace().next()?.split_once('/')?;
+            Some((name.to_string(), origins.contains("-security")))
+        })
+        .collect()
+}
+
+/// Parses `dnf check-update`, marking packages listed by `dnf updateinfo`
+/// as security updates
+fn dnf_pending() -> Vec<(String, bool)> {
+    parse_dnf(
+        &stdout_of(&["dnf", "-q", "check-update"]),
+        &stdout_of(&["dnf", "-q", "updateinfo", "list", "--security", "--updates"]),
+    )
+}
+
+fn parse_dnf(check_update: &str, updateinfo: &str) -> Vec<(String, bool)> {
+    // Lines are "advisory  severity/type  name-[epoch:]version-release.arch";
+    // dropping the arch and the last two dash-separated parts leaves the name
+    let security: Vec<&str> = updateinfo
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(2))
+        .filter_map(|nevra| nevra.rsplitn(3, '-').nth(2))
+        .collect();
+
+    check_update
+        .lines()
+        // Obsoleted packages are listed after a header and aren't updates themselves
+        .take_while(|line| !line.starts_with("Obsoleting"))
+        // "name.arch  version  repository"
+        .filter(|line| line.split_whitespace().count() == 3)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|package| {
+            let name = package.rsplit_once('.').map_or(package, |(name, _)| name);
+            (name.to_string(), security.contains(&name))
+        })
+        .collect()
+}
+
+/// Parses `apk list --upgradable`, e.g.
+/// "musl-1.2.5-r1 x86_64 {musl} (MIT) [upgradable from: musl-1.2.5-r0]"
+fn apk_pending() -> Vec<(String, bool)> {
+    parse_apk(&stdout_of(&["apk", "list", "--upgradable"]))
+}
+
+fn parse_apk(output: &str) -> Vec<(String, bool)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let origin = line.split('{').nth(1)?.split('}').next()?;
+            Some((origin.to_string(), false))
+        })
+        .collect()
+}
+
+/// Whether the running kernel's modules are gone, meaning it was upgraded in place
+fn kernel_replaced() -> bool {
+    modules_missing(
+        stdout_of(&["uname", "-r"]).trim(),
+        &[Path::new("/usr/lib/modules"), Path::new("/lib/modules")],
+    )
+}
+
+/// Whether none of `module_dirs` has modules for the kernel `release`
+fn modules_missing(release: &str, module_dirs: &[&Path]) -> bool {
+    !release.is_empty() && !module_dirs.iter().any(|dir| dir.join(release).exists())
+}
+
+fn reboot_required(manager: PackageManager) -> bool {
+    match manager {
+        PackageManager::Apt => Path::new("/var/run/reboot-required").exists(),
+        // Exits 1 when core libraries or the kernel were updated since boot
+        PackageManager::Dnf => Command::new("needs-restarting")
+            .arg("-r")
+            .output()
+            .is_ok_and(|output| output.status.code() == Some(1)),
+        PackageManager::Pacman | PackageManager::Apk => kernel_replaced(),
+    }
+}
+
+fn check(manager: PackageManager) -> PackageUpdates {
+    let pending = manager.pending();
+
+    PackageUpdates {
+        manager: manager.name().to_string(),
+        pending: pending.len() as u32,
+        security: pending.iter().filter(|(_, security)| *security).count() as u32,
+        reboot_required: reboot_required(manager),
+        packages: pending
+            .into_iter()
+            .take(MAX_PACKAGE_NAMES)
+            .map(|(name, _)| name)
+            .collect(),
+        checked_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64),
+    }
+}
+
+/// Starts checking for package updates every `SWARM_PACKAGE_CHECK_INTERVAL`
+/// seconds (`0` disables) and returns the shared result
+pub fn spawn_package_checker() -> SharedPackageUpdates {
+    let updates = SharedPackageUpdates::default();
+    let interval = env_secs("SWARM_PACKAGE_CHECK_INTERVAL", 6 * 3600);
+    if interval.is_zero() {
+        return updates;
+    }
+    let Some(manager) = PackageManager::detect() else {
+        return updates;
+    };
+    println!("Package manager: {}", manager.name());
+
+    let shared = updates.clone();
+    tokio::spawn(async move {
+        loop {
+            if let Ok(result) = tokio::task::spawn_blocking(move || check(manager)).await {
+                *shared.lock().unwrap() = Some(result);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(pending: &[(String, bool)]) -> Vec<(&str, bool)> {
+        pending
+            .iter()
+            .map(|(name, security)| (name.as_str(), *security))
+            .collect()
+    }
+
+    #[test]
+    fn parses_apt_list_upgradable() {
+        let output = "\
+Listing... Done
+openssl/jammy-updates,jammy-security 3.0.2-0ubuntu1.15 amd64 [upgradable from: 3.0.2-0ubuntu1.14]
+curl/jammy-updates 7.81.0-1ubuntu1.17 amd64 [upgradable from: 7.81.0-1ubuntu1.16]
+tzdata/jammy-updates 2024a-0ubuntu0.22.04.1 all [upgradable from: 2024a-0ubuntu0.22.04]
+";
+        assert_eq!(
+            names(&parse_apt(output)),
+            [("openssl", true), ("curl", false), ("tzdata", false)]
+        );
+        assert!(parse_apt("Listing... Done\n").is_empty());
+    }
+
+    #[test]
+    fn parses_dnf_check_update_with_security_advisories() {
+        let check_update = "
+kernel.x86_64                       5.14.0-427.22.1.el9_4        baseos
+openssl-libs.x86_64                 1:3.0.7-27.el9_4             baseos
+python3-urllib3.noarch              1.26.5-5.el9_4               appstream
+Obsoleting Packages
+grub2-tools.x86_64                  1:2.06-80.el9_4              baseos
+    grub2-tools.x86_64              1:2.06-77.el9                @baseos
+";
+        let updateinfo = "\
+RLSA-2024:3501 Important/Sec. kernel-5.14.0-427.22.1.el9_4.x86_64
+RLSA-2024:2447 Moderate/Sec.  openssl-libs-1:3.0.7-27.el9_4.x86_64
+";
+        assert_eq!(
+            names(&parse_dnf(check_update, updateinfo)),
+            [
+                ("kernel", true),
+                ("openssl-libs", true),
+                ("python3-urllib3", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_apk_list_upgradable() {
+        let output = "\
+musl-1.2.5-r1 x86_64 {musl} (MIT) [upgradable from: musl-1.2.5-r0]
+libcrypto3-3.3.1-r3 x86_64 {openssl} (Apache-2.0) [upgradable from: libcrypto3-3.3.1-r0]
+";
+        assert_eq!(
+            names(&parse_apk(output)),
+            [("musl", false), ("openssl", false)]
+        );
+    }
+
+    #[test]
+    fn parses_pacman_checkupdates() {
+        let output = "linux 6.9.1.arch1-1 -> 6.9.2.arch1-1\nglibc 2.39+r52-1 -> 2.39+r52-2\n\n";
+        assert_eq!(
+            names(&parse_first_words(output)),
+            [("linux", false), ("glibc", false)]
+        );
+    }
+
+    #[test]
+    fn reboot_is_required_once_the_running_kernels_modules_are_gone() {
+        let dir = std::env::temp_dir().join(format!("swarmreport-modules-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("6.9.2-arch1-1")).unwrap();
+
+        assert!(!modules_missing("6.9.2-arch1-1", &[&dir]));
+        assert!(modules_missing("6.9.1-arch1-1", &[&dir]));
+        assert!(!modules_missing(
+            "6.9.2-arch1-1",
+            &[Path::new("/nonexistent"), &dir]
+        ));
+        // Without a release there is nothing to compare
+        assert!(!modules_missing("", &[&dir]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
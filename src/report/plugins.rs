@@ -0,0 +1,455 @@
+/// External plugin support
+///
+/// Runs site-specific executables on their own interval and reports their
+/// results as check statuses and custom metrics. A plugin either prints JSON:
+///
+/// ```text
+/// {"status": "ok", "message": "on mains", "metrics": {"charge": 98, "runtime": {"value": 1800, "unit": "s"}}}
+/// ```
+///
+/// or follows the Nagios plugin convention, with the status taken from the
+/// exit code (0 OK, 1 WARNING, 2 CRITICAL, anything else UNKNOWN) and
+/// metrics from the performance data after `|`:
+///
+/// ```text
+/// OK - on mains|charge=98%;50;20 runtime=1800s
+/// ```
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::report::config::{env_list, env_secs};
+use crate::swarmreport::{CheckResult, CheckStatus, CustomMetric};
+
+/// Latest result per plugin name
+pub type SharedPluginResults = Arc<Mutex<HashMap<String, PluginResult>>>;
+
+#[derive(Clone, Debug)]
+pub struct PluginResult {
+    pub check: CheckResult,
+    pub metrics: Vec<CustomMetric>,
+}
+
+/// Shortest interval between runs, so a plugin never runs back to back
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A configured plugin executable
+#[derive(Clone, Debug)]
+struct Plugin {
+    name: String,
+    path: PathBuf,
+    interval: Duration,
+}
+
+/// Parses `SWARM_PLUGINS` entries of the form `name=/path/to/plugin[@seconds]`;
+/// an interval of 0 is rejected
+fn parse_plugin(entry: &str, default_interval: Duration) -> Option<Plugin> {
+    let (name, rest) = entry.split_once('=')?;
+    let (path, interval) = match rest.rsplit_once('@') {
+        Some((path, secs)) => {
+            let secs: u64 = secs.trim().parse().ok().filter(|secs| *secs > 0)?;
+            (path, Duration::from_secs(secs))
+        }
+        None => (rest, default_interval),
+    };
+
+    Some(Plugin {
+        name: name.trim().to_string(),
+        path: PathBuf::from(path.trim()),
+        interval,
+    })
+}
+
+/// Lists the executables in `SWARM_PLUGIN_DIR`, named after their file stem
+fn plugins_in_dir(dir: &Path, interval: Duration) -> Vec<Plugin> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut plugins: Vec<Plugin> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.metadata()
+                .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        })
+        .filter_map(|path| {
+            Some(Plugin {
+                name: path.file_stem()?.to_string_lossy().to_string(),
+                path,
+                interval,
+            })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+/// Reads the plugins configured through `SWARM_PLUGINS` and `SWARM_PLUGIN_DIR`
+fn configured_plugins() -> Vec<Plugin> {
+    let default_interval = env_secs("SWARM_PLUGIN_INTERVAL", 60).max(MIN_INTERVAL);
+
+    let mut plugins: Vec<Plugin> = env_list("SWARM_PLUGINS")
+        .iter()
+        .filter_map(|entry| {
+            let plugin = parse_plugin(entry, default_interval);
+            if plugin.is_none() {
+                eprintln!("Ignoring malformed plugin '{entry}'");
+            }
+            plugin
+        })
+        .collect();
+
+    if let Ok(dir) = std::env::var("SWARM_PLUGIN_DIR") {
+        for plugin in plugins_in_dir(Path::new(&dir), default_interval) {
+            if !plugins.iter().any(|p| p.name == plugin.name) {
+                plugins.push(plugin);
+            }
+        }
+    }
+
+    plugins
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn status_from_exit_code(code: Option<i32>) -> CheckStatus {
+    match code {
+        Some(0) => CheckStatus::Ok,
+        Some(1) => CheckStatus::Warning,
+        Some(2) => CheckStatus::Critical,
+        _ => CheckStatus::Unknown,
+    }
+}
+
+fn status_from_str(status: &str) -> CheckStatus {
+    match status.to_ascii_lowercase().as_str() {
+        "ok" => CheckStatus::Ok,
+        "warn" | "warning" => CheckStatus::Warning,
+        "crit" | "critical" => CheckStatus::Critical,
+        _ => CheckStatus::Unknown,
+    }
+}
+
+/// JSON plugin output
+#[derive(Deserialize)]
+struct JsonOutput {
+    status: Option<String>,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    metrics: HashMap<String, JsonMetric>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonMetric {
+    Value(f64),
+    WithUnit {
+        value: f64,
+        #[serde(default)]
+        unit: String,
+    },
+}
+
+/// Splits a Nagios performance value such as "98%" or "1.5s" into number and unit
+fn split_unit(value: &str) -> Option<(f64, String)> {
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+        .unwrap_or(value.len());
+    let number = value[..end].parse().ok()?;
+    Some((number, value[end..].to_string()))
+}
+
+/// Parses Nagios performance data: `'label'=value[unit];warn;crit;min;max ...`
+fn parse_perfdata(perfdata: &str) -> Vec<(String, f64, String)> {
+    let mut metrics = Vec::new();
+    let mut rest = perfdata.trim();
+
+    while !rest.is_empty() {
+        // Labels may be quoted to contain spaces
+        let (label, after_label) = if let Some(quoted) = rest.strip_prefix('\'') {
+            let Some((label, after)) = quoted.split_once("'=") else {
+                break;
+            };
+            (label, after)
+        } else {
+            let Some((label, after)) = rest.split_once('=') else {
+                break;
+            };
+            (label, after)
+        };
+
+        let (value, remainder) = after_label
+            .split_once(char::is_whitespace)
+            .unwrap_or((after_label, ""));
+        let value = value.split(';').next().unwrap_or_default();
+        if let Some((number, unit)) = split_unit(value) {
+            metrics.push((label.trim().to_string(), number, unit));
+        }
+        rest = remainder.trim_start();
+    }
+
+    metrics
+}
+
+/// Turns a plugin's output and exit code into its check result and metrics
+fn parse_output(name: &str, stdout: &str, exit_code: Option<i32>) -> PluginResult {
+    let metric = |label: &str, value: f64, unit: String| CustomMetric {
+        name: format!("{name}.{label}"),
+        value,
+        unit,
+    };
+    let check = |status: CheckStatus, message: String| CheckResult {
+        name: name.to_string(),
+        status: status.into(),
+        message,
+        checked_at: now(),
+        latency_ms: 0.0,
+    };
+
+    let trimmed = stdout.trim();
+    if trimmed.starts_with('{') {
+        return match serde_json::from_str::<JsonOutput>(trimmed) {
+            Ok(output) => {
+                let status = output
+                    .status
+                    .as_deref()
+                    .map_or_else(|| status_from_exit_code(exit_code), status_from_str);
+                let mut metrics: Vec<CustomMetric> = output
+                    .metrics
+                    .into_iter()
+                    .map(|(label, value)| match value {
+                        JsonMetric::Value(value) => metric(&label, value, String::new()),
+                        JsonMetric::WithUnit { value, unit } => metric(&label, value, unit),
+                    })
+                    .collect();
+                metrics.sort_by(|a, b| a.name.cmp(&b.name));
+                PluginResult {
+                    check: check(status, output.message),
+                    metrics,
+                }
+            }
+            Err(e) => PluginResult {
+                check: check(CheckStatus::Unknown, format!("invalid JSON output: {e}")),
+                metrics: vec![],
+            },
+        };
+    }
+
+    // Nagios: the first line is "text|perfdata"; later lines may carry more perfdata
+    let mut lines = trimmed.lines();
+    let first = lines.next().unwrap_or_default();
+    let (message, mut perfdata) = match first.split_once('|') {
+        Some((message, perfdata)) => (message.trim().to_string(), perfdata.to_string()),
+        None => (first.trim().to_string(), String::new()),
+    };
+    for line in lines {
+        if let Some((_, more)) = line.split_once('|') {
+            perfdata.push(' ');
+            perfdata.push_str(more);
+        }
+    }
+
+    PluginResult {
+        check: check(status_from_exit_code(exit_code), message),
+        metrics: parse_perfdata(&perfdata)
+            .into_iter()
+            .map(|(label, value, unit)| metric(&label, value, unit))
+            .collect(),
+    }
+}
+
+/// Runs a plugin once, giving up after `timeout`
+async fn run_plugin(plugin: &Plugin, timeout: Duration) -> PluginResult {
+    let command = tokio::process::Command::new(&plugin.path)
+        .kill_on_drop(true)
+        .output();
+
+    match tokio::time::timeout(timeout, command).await {
+        Ok(Ok(output)) => parse_output(
+            &plugin.name,
+            &String::from_utf8_lossy(&output.stdout),
+            output.status.code(),
+        ),
+        Ok(Err(e)) => parse_output(&plugin.name, &format!("failed to run: {e}"), None),
+        Err(_) => parse_output(
+            &plugin.name,
+            &format!("timed out after {}s", timeout.as_secs()),
+            None,
+        ),
+    }
+}
+
+/// Starts every configured plugin on its own interval and returns their
+/// shared latest results
+pub fn spawn_plugins() -> SharedPluginResults {
+    let results = SharedPluginResults::default();
+    let timeout = env_secs("SWARM_PLUGIN_TIMEOUT", 10);
+
+    for plugin in configured_plugins() {
+        println!(
+            "Plugin {}: {} every {}s",
+            plugin.name,
+            plugin.path.display(),
+            plugin.interval.as_secs()
+        );
+
+        let shared = results.clone();
+        tokio::spawn(async move {
+            loop {
+                let result = run_plugin(&plugin, timeout).await;
+                shared.lock().unwrap().insert(plugin.name.clone(), result);
+                tokio::time::sleep(plugin.interval).await;
+            }
+        });
+    }
+
+    results
+}
+
+/// Flattens the latest plugin results into report fields
+pub fn plugin_report(results: &SharedPluginResults) -> (Vec<CheckResult>, Vec<CustomMetric>) {
+    let results = results.lock().unwrap();
+    let mut names: Vec<&String> = results.keys().collect();
+    names.sort();
+
+    let checks = names
+        .iter()
+        .map(|name| results[*name].check.clone())
+        .collect();
+    let metrics = names
+        .iter()
+        .flat_map(|name| results[*name].metrics.clone())
+        .collect();
+    (checks, metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(result: &PluginResult) -> Vec<(&str, f64, &str)> {
+        result
+            .metrics
+            .iter()
+            .map(|metric| (metric.name.as_str(), metric.value, metric.unit.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_json_output() {
+        let result = parse_output(
+            "ups",
+            r#"{"status": "warn", "message": "on battery", "metrics": {"charge": {"value": 87, "unit": "%"}, "load": 0.42}}"#,
+            Some(0),
+        );
+        assert_eq!(result.check.name, "ups");
+        // The status field wins over the exit code
+        assert_eq!(result.check.status(), CheckStatus::Warning);
+        assert_eq!(result.check.message, "on battery");
+        assert_eq!(
+            metrics(&result),
+            [("ups.charge", 87.0, "%"), ("ups.load", 0.42, "")]
+        );
+
+        let without_status = parse_output("ups", r#"{"message": "fine"}"#, Some(2));
+        assert_eq!(without_status.check.status(), CheckStatus::Critical);
+        assert!(without_status.metrics.is_empty());
+    }
+
+    #[test]
+    fn parses_nagios_output_with_perfdata() {
+        let result = parse_output(
+            "disk",
+            "DISK OK - free space: / 3326 MB (56%);| '/ free'=3326MB;1000;500;0;5000 inodes=56%\n\
+             more detail | load1=0.5 'load 5'=0.3;;;0\n",
+            Some(0),
+        );
+        assert_eq!(result.check.status(), CheckStatus::Ok);
+        assert_eq!(
+            result.check.message,
+            "DISK OK - free space: / 3326 MB (56%);"
+        );
+        assert_eq!(
+            metrics(&result),
+            [
+                ("disk./ free", 3326.0, "MB"),
+                ("disk.inodes", 56.0, "%"),
+                ("disk.load1", 0.5, ""),
+                ("disk.load 5", 0.3, ""),
+            ]
+        );
+
+        let plain = parse_output("backup", "last backup 26h ago", Some(1));
+        assert_eq!(plain.check.message, "last backup 26h ago");
+        assert!(plain.metrics.is_empty());
+    }
+
+    #[test]
+    fn splits_perfdata_units() {
+        assert_eq!(split_unit("98%"), Some((98.0, "%".to_string())));
+        assert_eq!(split_unit("1.5s"), Some((1.5, "s".to_string())));
+        assert_eq!(split_unit("-3"), Some((-3.0, String::new())));
+        assert_eq!(split_unit("2e3B"), Some((2000.0, "B".to_string())));
+        assert_eq!(split_unit("U"), None);
+    }
+
+    #[test]
+    fn maps_exit_codes_to_statuses() {
+        let status = |code| parse_output("p", "text", code).check.status();
+        assert_eq!(status(Some(0)), CheckStatus::Ok);
+        assert_eq!(status(Some(1)), CheckStatus::Warning);
+        assert_eq!(status(Some(2)), CheckStatus::Critical);
+        assert_eq!(status(Some(3)), CheckStatus::Unknown);
+        assert_eq!(status(Some(127)), CheckStatus::Unknown);
+        // Killed by a signal
+        assert_eq!(status(None), CheckStatus::Unknown);
+    }
+
+    #[test]
+    fn malformed_output_is_unknown_or_skipped() {
+        let json = parse_output("p", r#"{"status": "ok", "metrics": [1, 2]"#, Some(0));
+        assert_eq!(json.check.status(), CheckStatus::Unknown);
+        assert!(json.check.message.starts_with("invalid JSON output"));
+
+        let unknown_status = parse_output("p", r#"{"status": "fine"}"#, Some(0));
+        assert_eq!(unknown_status.check.status(), CheckStatus::Unknown);
+
+        // Values that aren't numbers are dropped; an unterminated quote ends parsing
+        assert_eq!(
+            parse_perfdata("good=1 bad=U also=2s 'open=3"),
+            [
+                ("good".to_string(), 1.0, String::new()),
+                ("also".to_string(), 2.0, "s".to_string()),
+            ]
+        );
+        assert!(parse_perfdata("no metrics here").is_empty());
+        assert!(parse_output("p", "", Some(0)).metrics.is_empty());
+    }
+
+    #[test]
+    fn parses_plugin_entries() {
+        let default = Duration::from_secs(60);
+        let plugin = parse_plugin("ups = /usr/lib/swarm/ups.sh @ 30", default).unwrap();
+        assert_eq!(plugin.name, "ups");
+        assert_eq!(plugin.path, PathBuf::from("/usr/lib/swarm/ups.sh"));
+        assert_eq!(plugin.interval, Duration::from_secs(30));
+
+        let plugin = parse_plugin("backup=/opt/check_backup", default).unwrap();
+        assert_eq!(plugin.interval, default);
+
+        assert!(parse_plugin("/opt/check_backup", default).is_none());
+        assert!(parse_plugin("backup=/opt/check_backup@0", default).is_none());
+        assert!(parse_plugin("backup=/opt/check_backup@soon", default).is_none());
+    }
+}
@@ -0,0 +1,338 @@
+/// Active health probes
+///
+/// Runs HTTP(S), TCP and DNS probes from the node itself, so a container that
+/// is "running" but not serving shows up. Probes are configured with
+/// `SWARM_PROBES`, a comma-separated list of `name=target[;option=value...]`:
+///
+/// ```text
+/// web=https://localhost:8443/health;status=200;body=ok;warn_ms=500
+/// internal=https://10.0.0.5/health;insecure=true
+/// postgres=tcp://localhost:5432
+/// resolver=dns://example.com
+/// ```
+///
+/// Commas separate probes and semicolons separate options, so a URL that
+/// contains either must percent-encode it as `%2C` or `%3B`.
+///
+/// Results are attached to the service with the same name (or the one given
+/// with `service=`), and reported on their own when no service matches.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::report::config::{env_list, env_secs};
+use crate::swarmreport::{CheckResult, CheckStatus, Service};
+
+/// Shortest interval between runs, so a probe never runs back to back
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Latest result per probe name
+pub type SharedProbeResults = Arc<Mutex<HashMap<String, ProbeResult>>>;
+
+#[derive(Clone, Debug)]
+pub struct ProbeResult {
+    /// Service the result belongs to
+    pub service: String,
+    pub check: CheckResult,
+}
+
+#[derive(Clone, Debug)]
+enum Target {
+    Http(String),
+    Tcp(String),
+    Dns(String),
+}
+
+#[derive(Clone, Debug)]
+struct Probe {
+    name: String,
+    service: String,
+    target: Target,
+    interval: Duration,
+    /// Expected HTTP status; any 2xx or 3xx when unset
+    status: Option<u16>,
+    /// Text the HTTP body must contain
+    body: Option<String>,
+    /// Slower responses are reported as warnings
+    warn_after: Option<Duration>,
+    /// Accept any HTTPS certificate, for services with self-signed ones
+    insecure: bool,
+}
+
+/// Parses one `SWARM_PROBES` entry; an interval of 0 is rejected
+fn parse_probe(entry: &str, default_interval: Duration) -> Option<Probe> {
+    let (name, rest) = entry.split_once('=')?;
+    let mut parts = rest.split(';');
+    let target = parts.next()?.trim();
+
+    let target = if target.starts_with("http://") || target.starts_with("https://") {
+        Target::Http(target.to_string())
+    } else if let Some(address) = target.strip_prefix("tcp://") {
+        Target::Tcp(address.to_string())
+    } else if let Some(host) = target.strip_prefix("dns://") {
+        Target::Dns(host.to_string())
+    } else {
+        return None;
+    };
+
+    let mut probe = Probe {
+        name: name.trim().to_string(),
+        service: name.trim().to_string(),
+        target,
+        interval: default_interval,
+        status: None,
+        body: None,
+        warn_after: None,
+        insecure: false,
+    };
+
+    for option in parts {
+        let (key, value) = option.split_once('=')?;
+        let value = value.trim();
+        match key.trim() {
+            "service" => probe.service = value.to_string(),
+            "interval" => {
+                let secs: u64 = value.parse().ok().filter(|secs| *secs > 0)?;
+                probe.interval = Duration::from_secs(secs);
+            }
+            "status" => probe.status = Some(value.parse().ok()?),
+            "body" => probe.body = Some(value.to_string()),
+            "warn_ms" => probe.warn_after = Some(Duration::from_millis(value.parse().ok()?)),
+            "insecure" => probe.insecure = value.parse().ok()?,
+            _ => return None,
+        }
+    }
+
+    Some(probe)
+}
+
+/// Runs an HTTP probe, returning the failure reason or a success summary
+async fn probe_http(client: &reqwest::Client, url: &str, probe: &Probe) -> Result<String, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+
+    let status_ok = match probe.status {
+        Some(expected) => status.as_u16() == expected,
+        None => status.is_success() || status.is_redirection(),
+    };
+    if !status_ok {
+        return Err(format!("HTTP {status}"));
+    }
+
+    if let Some(expected) = &probe.body {
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        if !body.contains(expected.as_str()) {
+            return Err(format!("HTTP {status}, body missing \"{expected}\""));
+        }
+    }
+
+    Ok(format!("HTTP {status}"))
+}
+
+async fn probe_tcp(address: &str) -> Result<String, String> {
+    tokio::net::TcpStream::connect(address)
+        .await
+        .map(|_| "connected".to_string())
+        .map_err(|e| e.to_string())
+}
+
+async fn probe_dns(host: &str) -> Result<String, String> {
+    let addresses: Vec<_> = tokio::net::lookup_host((host, 0))
+        .await
+        .map_err(|e| e.to_string())?
+        .collect();
+    match addresses.len() {
+        0 => Err("no addresses".to_string()),
+        1 => Ok(format!("resolved to {}", addresses[0].ip())),
+        count => Ok(format!(
+            "resolved to {} (+{} more)",
+            addresses[0].ip(),
+            count - 1
+        )),
+    }
+}
+
+/// Runs a probe once, giving up after `timeout`
+async fn run_probe(client: &reqwest::Client, probe: &Probe, timeout: Duration) -> CheckResult {
+    let started = Instant::now();
+    let attempt = async {
+        match &probe.target {
+            Target::Http(url) => probe_http(client, url, probe).await,
+            Target::Tcp(address) => probe_tcp(address).await,
+            Target::Dns(host) => probe_dns(host).await,
+        }
+    };
+    let outcome = tokio::time::timeout(timeout, attempt)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", timeout.as_secs())));
+    let latency = started.elapsed();
+
+    let (status, message) = match outcome {
+        Ok(message) if probe.warn_after.is_some_and(|limit| latency > limit) => {
+            (CheckStatus::Warning, format!("{message}, slow"))
+        }
+        Ok(message) => (CheckStatus::Ok, message),
+        Err(message) => (CheckStatus::Critical, message),
+    };
+
+    CheckResult {
+        name: probe.name.clone(),
+        status: status.into(),
+        message,
+        checked_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64),
+        latency_ms: latency.as_secs_f64() * 1000.0,
+    }
+}
+
+/// Starts every probe in `SWARM_PROBES` on its own interval and returns their
+/// shared latest results
+pub fn spawn_probes() -> SharedProbeResults {
+    let results = SharedProbeResults::default();
+    let default_interval = env_secs("SWARM_PROBE_INTERVAL", 30).max(MIN_INTERVAL);
+    let timeout = env_secs("SWARM_PROBE_TIMEOUT", 5);
+
+    let probes: Vec<Probe> = env_list("SWARM_PROBES")
+        .iter()
+        .filter_map(|entry| {
+            let probe = parse_probe(entry, default_interval);
+            if probe.is_none() {
+                eprintln!("Ignoring malformed probe '{entry}'");
+            }
+            probe
+        })
+        .collect();
+    if probes.is_empty() {
+        return results;
+    }
+
+    // Certificates are verified unless a probe opts out with `insecure=true`,
+    // e.g. for an internal service with a self-signed certificate
+    let clients = reqwest::Client::builder().build().and_then(|verified| {
+        let insecure = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()?;
+        Ok((verified, insecure))
+    });
+    let (verified, insecure) = match clients {
+        Ok(clients) => clients,
+        Err(e) => {
+            eprintln!("Probes disabled: {e}");
+            return results;
+        }
+    };
+
+    for probe in probes {
+        println!(
+            "Probe {}: {:?} every {}s",
+            probe.name,
+            probe.target,
+            probe.interval.as_secs()
+        );
+
+        let shared = results.clone();
+        let client = if probe.insecure {
+            insecure.clone()
+        } else {
+            verified.clone()
+        };
+        tokio::spawn(async move {
+            loop {
+                let check = run_probe(&client, &probe, timeout).await;
+                shared.lock().unwrap().insert(
+                    probe.name.clone(),
+                    ProbeResult {
+                        service: probe.service.clone(),
+                        check,
+                    },
+                );
+                tokio::time::sleep(probe.interval).await;
+            }
+        });
+    }
+
+    results
+}
+
+/// Attaches probe results to their services, returning the ones that match
+/// no service (systemd units match with or without their ".service" suffix)
+pub fn attach_probes(results: &SharedProbeResults, services: &mut [Service]) -> Vec<CheckResult> {
+    let results = results.lock().unwrap();
+    let mut names: Vec<&String> = results.keys().collect();
+    names.sort();
+
+    let mut unmatched = Vec::new();
+    for name in names {
+        let result = &results[name];
+        let service = services.iter_mut().find(|service| {
+            service.name == result.service
+                || service.name.strip_suffix(".service") == Some(result.service.as_str())
+        });
+        match service {
+            Some(service) => service.probes.push(result.check.clone()),
+            None => unmatched.push(result.check.clone()),
+        }
+    }
+    unmatched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn parses_http_probes_with_options() {
+        let probe = parse_probe(
+            " web = https://localhost:8443/health?checks=db%2Ccache ; status=204; body=ok ;warn_ms=500;service=nginx;insecure=true",
+            DEFAULT,
+        )
+        .unwrap();
+        assert_eq!(
+            (probe.name.as_str(), probe.service.as_str()),
+            ("web", "nginx")
+        );
+        assert!(
+            matches!(&probe.target, Target::Http(url) if url == "https://localhost:8443/health?checks=db%2Ccache")
+        );
+        assert_eq!(probe.status, Some(204));
+        assert_eq!(probe.body.as_deref(), Some("ok"));
+        assert_eq!(probe.warn_after, Some(Duration::from_millis(500)));
+        assert!(probe.insecure);
+        assert_eq!(probe.interval, DEFAULT);
+
+        let plain = parse_probe("api=http://10.0.0.5:8080/", DEFAULT).unwrap();
+        assert_eq!(plain.service, "api");
+        assert_eq!((plain.status, plain.insecure), (None, false));
+    }
+
+    #[test]
+    fn parses_tcp_and_dns_probes() {
+        let tcp = parse_probe("postgres=tcp://localhost:5432", DEFAULT).unwrap();
+        assert!(matches!(&tcp.target, Target::Tcp(address) if address == "localhost:5432"));
+
+        let dns = parse_probe("resolver=dns://example.com;interval=300", DEFAULT).unwrap();
+        assert!(matches!(&dns.target, Target::Dns(host) if host == "example.com"));
+        assert_eq!(dns.interval, Duration::from_secs(300));
+    }
+
+    #[test]
+    fn rejects_bad_entries() {
+        for entry in [
+            "no-target",
+            "web=ftp://localhost/",
+            "web=localhost:80",
+            "web=http://localhost/;interval=0",
+            "web=http://localhost/;interval=soon",
+            "web=http://localhost/;status=ok",
+            "web=http://localhost/;warn_ms=-1",
+            "web=http://localhost/;insecure=yes",
+            "web=http://localhost/;retries=3",
+            "web=http://localhost/;body",
+        ] {
+            assert!(parse_probe(entry, DEFAULT).is_none(), "{entry}");
+        }
+    }
+}
@@ -0,0 +1,107 @@
+/// Top process collection
+///
+/// Reports the processes using the most CPU and memory so a spike seen in the
+/// sentinel can be traced to its cause. `SWARM_TOP_PROCESSES` sets how many
+/// are reported per list; `0` disables the collector.
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind, Users};
+
+use crate::swarmreport::ProcessInfo;
+
+/// Processes reported per list unless `SWARM_TOP_PROCESSES` says otherwise
+const DEFAULT_TOP_PROCESSES: usize = 5;
+
+/// Command lines longer than this are cut off
+const MAX_COMMAND_LEN: usize = 200;
+
+fn truncate(command: String) -> String {
+    match command.char_indices().nth(MAX_COMMAND_LEN) {
+        Some((index, _)) => format!("{}…", &command[..index]),
+        None => command,
+    }
+}
+
+/// Keeps the process table between reports so CPU usage covers the interval
+pub struct ProcessCollector {
+    sys: System,
+    users: Users,
+    limit: usize,
+}
+
+impl ProcessCollector {
+    pub fn new() -> Self {
+        let limit = std::env::var("SWARM_TOP_PROCESSES")
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_TOP_PROCESSES);
+
+        Self {
+            sys: System::new(),
+            users: Users::new_with_refreshed_list(),
+            limit,
+        }
+    }
+
+    fn to_info(&self, process: &sysinfo::Process) -> ProcessInfo {
+        let user = process.user_id().map_or_else(String::new, |uid| {
+            self.users
+                .get_user_by_id(uid)
+                .map_or_else(|| uid.to_string(), |user| user.name().to_string())
+        });
+        let command = process
+            .cmd()
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        ProcessInfo {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().to_string(),
+            user,
+            cpu_percent: process.cpu_usage() as f64,
+            memory_bytes: process.memory(),
+            command: truncate(command),
+        }
+    }
+
+    /// Returns the top processes by CPU and by memory, both empty when disabled
+    pub fn collect(&mut self) -> (Vec<ProcessInfo>, Vec<ProcessInfo>) {
+        if self.limit == 0 {
+            return (vec![], vec![]);
+        }
+
+        self.sys.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_cpu()
+                .with_memory()
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_user(UpdateKind::OnlyIfNotSet),
+        );
+
+        // Threads show up as processes on Linux; only report the processes themselves
+        let mut processes: Vec<&sysinfo::Process> = self
+            .sys
+            .processes()
+            .values()
+            .filter(|process| process.thread_kind().is_none())
+            .collect();
+
+        processes.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()));
+        let top_cpu = processes
+            .iter()
+            .take(self.limit)
+            .map(|process| self.to_info(process))
+            .collect();
+
+        processes.sort_by_key(|process| std::cmp::Reverse(process.memory()));
+        let top_memory = processes
+            .iter()
+            .take(self.limit)
+            .map(|process| self.to_info(process))
+            .collect();
+
+        (top_cpu, top_memory)
+    }
+}
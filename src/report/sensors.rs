@@ -0,0 +1,46 @@
+/// Hardware sensor collection
+///
+/// Reads temperature sensors through sysinfo's `Components`. Sensors without
+/// a current reading are skipped; nodes without any (most VMs) report none.
+use sysinfo::Components;
+
+use crate::swarmreport::Temperature;
+
+/// Keeps the sensor list between reports so max readings accumulate
+pub struct SensorCollector {
+    components: Components,
+}
+
+impl SensorCollector {
+    pub fn new() -> Self {
+        Self {
+            components: Components::new_with_refreshed_list(),
+        }
+    }
+
+    /// Refreshes and returns every sensor with a current reading
+    pub fn collect(&mut self) -> Vec<Temperature> {
+        self.components.refresh(false);
+
+        let mut temperatures: Vec<Temperature> = self
+            .components
+            .list()
+            .iter()
+            .filter_map(|component| {
+                let current = component.temperature().filter(|t| t.is_finite())?;
+                Some(Temperature {
+                    label: component.label().to_string(),
+                    current_celsius: current as f64,
+                    max_celsius: component.max().filter(|t| t.is_finite()).unwrap_or(0.0) as f64,
+                    critical_celsius: component
+                        .critical()
+                        .filter(|t| t.is_finite())
+                        .unwrap_or(0.0) as f64,
+                })
+            })
+            .collect();
+
+        temperatures.sort_by(|a, b| a.label.cmp(&b.label));
+        temperatures
+    }
+}
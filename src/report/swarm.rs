@@ -0,0 +1,260 @@
+/// Docker Swarm collector
+///
+/// When the reporter runs on a Docker Swarm manager, periodically collects the
+/// cluster's services, their replica counts and task placement, and the state
+/// of every node. Workers and standalone engines report nothing.
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::report::config::env_secs;
+use crate::swarmreport::{DockerSwarm, SwarmNode, SwarmService, SwarmTask};
+
+/// Latest cluster state, None when this node is not a swarm manager
+pub type SharedDockerSwarm = Arc<Mutex<Option<DockerSwarm>>>;
+
+/// The `.Swarm` section of `docker info`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SwarmInfo {
+    #[serde(rename = "NodeID")]
+    node_id: String,
+    local_node_state: String,
+    control_available: bool,
+    cluster: Option<ClusterInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ClusterInfo {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+/// A service as printed by `docker service ls --format '{{json .}}'`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ServiceLine {
+    #[serde(rename = "ID")]
+    id: String,
+    name: String,
+    image: String,
+    mode: String,
+    replicas: String,
+}
+
+/// A task as printed by `docker service ps --format '{{json .}}'`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TaskLine {
+    name: String,
+    node: String,
+    current_state: String,
+    #[serde(default)]
+    error: String,
+}
+
+/// A node as printed by `docker node ls --format '{{json .}}'`
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct NodeLine {
+    #[serde(rename = "ID")]
+    id: String,
+    hostname: String,
+    status: String,
+    availability: String,
+    #[serde(default)]
+    manager_status: String,
+    #[serde(default)]
+    engine_version: String,
+}
+
+/// Runs a docker command and parses each output line as JSON
+fn docker_json_lines<T: for<'de> Deserialize<'de>>(args: &[&str]) -> Vec<T> {
+    match Command::new("docker").args(args).output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Parses a replica column such as "2/3" or "1/1 (max 1 per node)"
+fn parse_replicas(replicas: &str) -> (u32, u32) {
+    let counts = replicas.split_whitespace().next().unwrap_or_default();
+    let (running, desired) = counts.split_once('/').unwrap_or(("0", "0"));
+    (running.parse().unwrap_or(0), desired.parse().unwrap_or(0))
+}
+
+/// Collects the cluster state if this engine is an active swarm manager
+fn collect_swarm() -> Option<DockerSwarm> {
+    let output = Command::new("docker")
+        .args(["info", "--format", "{{json .Swarm}}"])
+        .output()
+        .ok()?;
+    let info: SwarmInfo = serde_json::from_slice(&output.stdout).ok()?;
+    if info.local_node_state != "active" || !info.control_available {
+        return None;
+    }
+
+    let service_lines: Vec<ServiceLine> =
+        docker_json_lines(&["service", "ls", "--format", "{{json .}}"]);
+
+    let mut task_args = vec![
+        "service",
+        "ps",
+        "--no-trunc",
+        "--filter",
+        "desired-state=running",
+        "--format",
+        "{{json .}}",
+    ];
+    task_args.extend(service_lines.iter().map(|s| s.id.as_str()));
+    let tasks: Vec<TaskLine> = if service_lines.is_empty() {
+        vec![]
+    } else {
+        docker_json_lines(&task_args)
+    };
+
+    let services = service_lines
+        .into_iter()
+        .map(|service| {
+            let (running_replicas, desired_replicas) = parse_replicas(&service.replicas);
+            // Task names are "<service>.<slot>" or "<service>.<node id>" for global services
+            let prefix = format!("{}.", service.name);
+            let tasks = tasks
+                .iter()
+                .filter(|task| task.name.starts_with(&prefix))
+                .map(|task| SwarmTask {
+                    name: task.name.clone(),
+                    node: task.node.clone(),
+                    state: task.current_state.clone(),
+                    error: task.error.clone(),
+                })
+                .collect();
+
+            SwarmService {
+                id: service.id,
+                name: service.name,
+                image: service.image,
+                mode: service.mode,
+                desired_replicas,
+                running_replicas,
+                tasks,
+            }
+        })
+        .collect();
+
+    let nodes = docker_json_lines::<NodeLine>(&["node", "ls", "--format", "{{json .}}"])
+        .into_iter()
+        .map(|node| SwarmNode {
+            id: node.id,
+            hostname: node.hostname,
+            status: node.status,
+            availability: node.availability,
+            manager_status: node.manager_status,
+            engine_version: node.engine_version,
+        })
+        .collect();
+
+    Some(DockerSwarm {
+        cluster_id: info.cluster.map(|c| c.id).unwrap_or_default(),
+        node_id: info.node_id,
+        services,
+        nodes,
+    })
+}
+
+/// Starts polling the swarm state every `SWARM_DOCKER_SWARM_INTERVAL` seconds
+/// (`0` disables) and returns the shared result
+pub fn spawn_swarm_collector() -> SharedDockerSwarm {
+    let swarm = SharedDockerSwarm::default();
+    let interval = env_secs("SWARM_DOCKER_SWARM_INTERVAL", 10);
+    if interval.is_zero() {
+        return swarm;
+    }
+
+    let shared = swarm.clone();
+    tokio::spawn(async move {
+        loop {
+            let state = tokio::task::spawn_blocking(collect_swarm)
+                .await
+                .unwrap_or_default();
+            *shared.lock().unwrap() = state;
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    swarm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_replica_columns() {
+        assert_eq!(parse_replicas("2/3"), (2, 3));
+        assert_eq!(parse_replicas("1/1 (max 1 per node)"), (1, 1));
+        // Global services in older engines, and anything unexpected
+        assert_eq!(parse_replicas("global"), (0, 0));
+        assert_eq!(parse_replicas(""), (0, 0));
+        assert_eq!(parse_replicas("x/3"), (0, 3));
+    }
+
+    #[test]
+    fn parses_docker_info_swarm_section() {
+        let manager: SwarmInfo = serde_json::from_str(
+            r#"{"NodeID":"k3v9vq2x8d1nq4h7ul0w3zq0r","NodeAddr":"10.0.0.11","LocalNodeState":"active","ControlAvailable":true,"Error":"","RemoteManagers":null,"Nodes":3,"Managers":1,"Cluster":{"ID":"x8v5m2c0kq7f3j9h1w4t6y8zu","Version":{"Index":42}}}"#,
+        )
+        .unwrap();
+        assert_eq!(manager.node_id, "k3v9vq2x8d1nq4h7ul0w3zq0r");
+        assert!(manager.control_available);
+        assert_eq!(manager.cluster.unwrap().id, "x8v5m2c0kq7f3j9h1w4t6y8zu");
+
+        let standalone: SwarmInfo = serde_json::from_str(
+            r#"{"NodeID":"","NodeAddr":"","LocalNodeState":"inactive","ControlAvailable":false,"Error":"","RemoteManagers":null}"#,
+        )
+        .unwrap();
+        assert_eq!(standalone.local_node_state, "inactive");
+        assert!(standalone.cluster.is_none());
+    }
+
+    #[test]
+    fn parses_service_task_and_node_lines() {
+        let service: ServiceLine = serde_json::from_str(
+            r#"{"ID":"q1w2e3r4t5y6","Image":"nginx:1.27","Mode":"replicated","Name":"web_nginx","Ports":"*:8080->80/tcp","Replicas":"2/3"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            (
+                service.name.as_str(),
+                service.mode.as_str(),
+                service.replicas.as_str()
+            ),
+            ("web_nginx", "replicated", "2/3")
+        );
+
+        let task: TaskLine = serde_json::from_str(
+            r#"{"CurrentState":"Rejected 3 seconds ago","DesiredState":"Running","Error":"\"no suitable node\"","ID":"a1b2c3","Image":"nginx:1.27","Name":"web_nginx.3","Node":"","Ports":""}"#,
+        )
+        .unwrap();
+        assert_eq!(task.name, "web_nginx.3");
+        assert_eq!(task.error, "\"no suitable node\"");
+
+        let node: NodeLine = serde_json::from_str(
+            r#"{"Availability":"Active","EngineVersion":"27.3.1","Hostname":"worker-1","ID":"z9y8x7","ManagerStatus":"","Self":false,"Status":"Ready","TLSStatus":"Ready"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            (
+                node.hostname.as_str(),
+                node.status.as_str(),
+                node.engine_version.as_str()
+            ),
+            ("worker-1", "Ready", "27.3.1")
+        );
+    }
+}
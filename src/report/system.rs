@@ -1,20 +1,40 @@
+use crate::report::certs::{SharedCertificates, spawn_cert_checker};
 /// System information collection for SwarmReport
 ///
-/// This module gathers system metrics including CPU, memory, disk usage,
-/// IP address, and running Docker services.
-use crate::swarmreport::{Service, SystemReport};
+/// This module gathers system metrics including CPU load, memory, disk usage,
+/// temperatures, top processes, IP address, running containers, systemd units
+/// pending package updates, plugin and probe results, certificates and
+/// Tailscale status.
+use crate::report::containers::{ContainerRuntime, get_container_services};
+use crate::report::cpu::CpuCollector;
+use crate::report::disks::{DiskFilter, get_disks};
+use crate::report::inventory::InventorySchedule;
+use crate::report::memory::get_memory;
+use crate::report::network::{NetworkCollector, ip_addresses};
+use crate::report::packages::{SharedPackageUpdates, spawn_package_checker};
+use crate::report::plugins::{SharedPluginResults, plugin_report, spawn_plugins};
+use crate::report::probes::{SharedProbeResults, attach_probes, spawn_probes};
+use crate::report::processes::ProcessCollector;
+use crate::report::sensors::SensorCollector;
+use crate::report::swarm::{SharedDockerSwarm, spawn_swarm_collector};
+use crate::report::systemd::{configured_units, get_systemd_services};
+use crate::report::tailscale::{SharedTailscaleStatus, spawn_tailscale_collector};
+use crate::report::updates::{SharedImageUpdates, spawn_update_checker};
+use crate::swarmreport::{DiskInfo, MemoryInfo, SystemReport, TailscaleStatus};
 use local_ip_address::local_ip;
-use sysinfo::{Disks, System};
-
-/// Gets the system's IP address, preferring Tailscale if available
-fn get_ip_address() -> String {
-    // Try to get Tailscale IP first
-    if let Ok(output) = std::process::Command::new("tailscale").arg("ip").output() {
-        if output.status.success() {
-            if let Ok(ip) = String::from_utf8(output.stdout) {
-                return ip.lines().next().unwrap_or("unknown").to_string();
-            }
-        }
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysinfo::System;
+
+/// Gets the system's IP address, preferring the Tailscale address (IPv4
+/// first) from the latest Tailscale status if available
+fn get_ip_address(tailscale: Option<&TailscaleStatus>) -> String {
+    let addresses = tailscale.map_or(&[][..], |status| &status.addresses[..]);
+    if let Some(ip) = addresses
+        .iter()
+        .find(|ip| !ip.contains(':'))
+        .or(addresses.first())
+    {
+        return ip.clone();
     }
 
     // Fall back to local IP
@@ -23,78 +43,43 @@ fn get_ip_address() -> String {
         .unwrap_or_else(|_| "unknown".to_string())
 }
 
-/// Formats bytes into human-readable storage units (GB or TB)
+/// Formats bytes into human-readable storage units (GiB or TiB)
 fn format_storage_size(bytes: f64) -> String {
-    const GB: f64 = 1024.0 * 1024.0 * 1000.0;
-    const TB: f64 = GB * 1000.0;
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const TIB: f64 = GIB * 1024.0;
 
-    if bytes > TB {
-        format!("{:.2} TB", bytes / TB)
+    if bytes > TIB {
+        format!("{:.2} TiB", bytes / TIB)
     } else {
-        format!("{:.2} GB", bytes / GB)
+        format!("{:.2} GiB", bytes / GIB)
     }
 }
 
-/// Gets total disk usage across all mounted disks
-fn get_disk_usage() -> String {
-    let disks = Disks::new_with_refreshed_list();
+/// Sums usage over the reported disks as "used / total"
+fn get_disk_usage(disks: &[DiskInfo]) -> String {
     if disks.is_empty() {
         return "unknown".to_string();
     }
 
-    let (total_space, available_space): (f64, f64) = disks
-        .list()
-        .iter()
-        .map(|disk| (disk.total_space() as f64, disk.available_space() as f64))
-        .fold(
-            (0.0, 0.0),
-            |(total, available), (disk_total, disk_available)| {
-                (total + disk_total, available + disk_available)
-            },
-        );
-
-    let used_space = total_space - available_space;
+    let total_space: u64 = disks.iter().map(|disk| disk.total_bytes).sum();
+    let used_space: u64 = disks.iter().map(|disk| disk.used_bytes).sum();
+
     format!(
         "{} / {}",
-        format_storage_size(used_space),
-        format_storage_size(total_space)
+        format_storage_size(used_space as f64),
+        format_storage_size(total_space as f64)
     )
 }
 
-/// Formats memory usage as "used/total GB"
-fn get_memory_usage(sys: &System) -> String {
-    const GB: f64 = 1024.0 * 1024.0 * 1000.0;
-    let used_gb = sys.used_memory() as f64 / GB;
-    let total_gb = (sys.total_memory() as f64 / GB).round();
-    format!("{used_gb:.1}/{total_gb} GB")
-}
-
-/// Gets running Docker containers as services
-/// Returns empty vector if Docker is not available or no containers are running
-fn get_services() -> Vec<Service> {
-    let output = match std::process::Command::new("docker").arg("ps").output() {
-        Ok(output) if output.status.success() => output,
-        _ => return vec![], // Docker not available or command failed
-    };
-
-    let stdout = String::from_utf8(output.stdout).unwrap_or_default();
-
-    stdout
-        .lines()
-        .skip(1) // Skip header line
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                Some(Service {
-                    name: parts[1].to_string(),
-                    status: "running".to_string(),
-                    needs_update: false, // TODO: Implement update checking
-                })
-            } else {
-                None
-            }
-        })
-        .collect()
+/// Formats memory usage as "used / total GiB" for sentinels that predate
+/// the structured `memory` field
+fn get_memory_usage(memory: &MemoryInfo) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    format!(
+        "{:.1} GiB / {:.1} GiB",
+        memory.used_bytes as f64 / GIB,
+        memory.total_bytes as f64 / GIB
+    )
 }
 
 /// Creates a unique node identifier from system information
@@ -105,24 +90,121 @@ fn create_node_id() -> String {
     format!("{hostname}-{os_name}-{os_version}")
 }
 
-/// Collects and returns a complete system report
-pub fn get_system_report() -> SystemReport {
-    let mut sys = System::new();
-
-    // Refresh only the data we need
-    sys.refresh_cpu_usage();
-    // Wait a bit for CPU usage calculation to be accurate
-    std::thread::sleep(std::time::Duration::from_millis(200));
-    sys.refresh_cpu_usage();
-    sys.refresh_memory();
-
-    SystemReport {
-        node_id: create_node_id(),
-        hostname: System::host_name().unwrap_or_else(|| "unknown".to_string()),
-        ip_address: get_ip_address(),
-        cpu_usage: format!("{:.1}%", sys.global_cpu_usage()),
-        memory_usage: get_memory_usage(&sys),
-        disk_usage: get_disk_usage(),
-        services: get_services(),
+/// Current Unix time in milliseconds
+pub fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+/// Long-lived collector that owns the background checks feeding each report
+pub struct Collector {
+    runtimes: Vec<ContainerRuntime>,
+    image_updates: SharedImageUpdates,
+    systemd_units: Vec<String>,
+    docker_swarm: SharedDockerSwarm,
+    disk_filter: DiskFilter,
+    network: NetworkCollector,
+    cpu: CpuCollector,
+    sensors: SensorCollector,
+    processes: ProcessCollector,
+    inventory: InventorySchedule,
+    package_updates: SharedPackageUpdates,
+    plugins: SharedPluginResults,
+    probes: SharedProbeResults,
+    certificates: SharedCertificates,
+    tailscale: SharedTailscaleStatus,
+    /// Sequence number of the last collected report
+    sequence: u64,
+    started_at: i64,
+}
+
+impl Collector {
+    /// Creates the collector and starts its background checks
+    pub fn new() -> Self {
+        let runtimes = ContainerRuntime::from_env();
+        println!("Container runtimes: {runtimes:?}");
+
+        Self {
+            image_updates: spawn_update_checker(runtimes.clone()),
+            runtimes,
+            systemd_units: configured_units(),
+            docker_swarm: spawn_swarm_collector(),
+            disk_filter: DiskFilter::from_env(),
+            network: NetworkCollector::new(),
+            cpu: CpuCollector::new(),
+            sensors: SensorCollector::new(),
+            processes: ProcessCollector::new(),
+            inventory: InventorySchedule::from_env(),
+            package_updates: spawn_package_checker(),
+            plugins: spawn_plugins(),
+            probes: spawn_probes(),
+            certificates: spawn_cert_checker(),
+            tailscale: spawn_tailscale_collector(),
+            sequence: 0,
+            started_at: unix_millis(),
+        }
+    }
+
+    /// Makes the next report carry the host inventory again
+    pub fn retry_inventory(&mut self) {
+        self.inventory.retry();
+    }
+
+    /// Collects and returns a complete system report
+    pub fn collect(&mut self) -> SystemReport {
+        let mut sys = System::new();
+
+        // Refresh only the data we need
+        sys.refresh_cpu_usage();
+        // Wait a bit for CPU usage calculation to be accurate
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        sys.refresh_cpu_usage();
+        sys.refresh_memory();
+
+        let mut services = get_container_services(&self.runtimes, &self.image_updates);
+        services.extend(get_systemd_services(&self.systemd_units));
+
+        self.sequence += 1;
+        let disks = get_disks(&self.disk_filter);
+        let network_interfaces = self.network.collect();
+        let memory = get_memory(&sys);
+        let (top_cpu_processes, top_memory_processes) = self.processes.collect();
+        let (mut checks, custom_metrics) = plugin_report(&self.plugins);
+        checks.extend(attach_probes(&self.probes, &mut services));
+
+        let tailscale = self.tailscale.lock().unwrap().clone();
+
+        SystemReport {
+            node_id: create_node_id(),
+            collected_at: unix_millis(),
+            sequence: self.sequence,
+            reporter_started_at: self.started_at,
+            sent_at: 0,
+            // Set by the sentinel
+            site: String::new(),
+            received_at: 0,
+            hostname: System::host_name().unwrap_or_else(|| "unknown".to_string()),
+            ip_address: get_ip_address(tailscale.as_ref()),
+            cpu_usage: format!("{:.1}%", sys.global_cpu_usage()),
+            memory_usage: get_memory_usage(&memory),
+            memory: Some(memory),
+            disk_usage: get_disk_usage(&disks),
+            disks,
+            addresses: ip_addresses(&network_interfaces),
+            network_interfaces,
+            cpu: Some(self.cpu.collect(&sys)),
+            temperatures: self.sensors.collect(),
+            top_cpu_processes,
+            top_memory_processes,
+            inventory: self.inventory.next(),
+            package_updates: self.package_updates.lock().unwrap().clone(),
+            custom_metrics,
+            checks,
+            certificates: self.certificates.lock().unwrap().clone(),
+            tailscale,
+            services,
+            docker_swarm: self.docker_swarm.lock().unwrap().clone(),
+        }
     }
 }
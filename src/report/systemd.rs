@@ -0,0 +1,198 @@
+/// systemd unit collector
+///
+/// Reports the state of systemd units as services. Units are chosen with
+/// `SWARM_SYSTEMD_UNITS`, a comma-separated list where the special entry
+/// "failed" expands to every unit currently in the failed state.
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::report::config::env_list;
+use crate::swarmreport::{Service, ServiceKind};
+
+const PROPERTIES: &str =
+    "Id,ActiveState,SubState,NRestarts,StateChangeTimestamp,ActiveEnterTimestamp";
+
+/// Returns the units to report, as configured by `SWARM_SYSTEMD_UNITS`
+pub fn configured_units() -> Vec<String> {
+    match std::env::var("SWARM_SYSTEMD_UNITS") {
+        Ok(_) => env_list("SWARM_SYSTEMD_UNITS"),
+        Err(_) => vec!["failed".to_string()],
+    }
+}
+
+/// Lists all units systemd currently considers failed
+fn failed_units() -> Vec<String> {
+    let output = match Command::new("systemctl")
+        .args([
+            "list-units",
+            "--state=failed",
+            "--plain",
+            "--no-legend",
+            "--no-pager",
+        ])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return vec![],
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Runs `systemctl show` for the units, preferring unix timestamps when the
+/// installed systemd supports `--timestamp=unix` (v248+)
+fn show_units(units: &[String]) -> Option<String> {
+    let property_arg = format!("--property={PROPERTIES}");
+
+    for extra in [Some("--timestamp=unix"), None] {
+        let mut command = Command::new("systemctl");
+        command.args(["show", "--no-pager", &property_arg]);
+        command.args(extra);
+        command.args(units);
+
+        if let Ok(output) = command.output()
+            && output.status.success()
+        {
+            return String::from_utf8(output.stdout).ok();
+        }
+    }
+
+    None
+}
+
+/// Parses one `systemctl show` block of key=value lines into a service
+fn parse_unit(block: &str) -> Option<Service> {
+    let properties: HashMap<&str, &str> = block
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect();
+
+    let name = properties.get("Id")?.to_string();
+    let timestamp = |property: &str| {
+        properties
+            .get(property)
+            .and_then(|ts| ts.strip_prefix('@'))
+            .and_then(|ts| ts.parse().ok())
+            .unwrap_or(0)
+    };
+
+    Some(Service {
+        name,
+        status: properties
+            .get("ActiveState")
+            .unwrap_or(&"unknown")
+            .to_string(),
+        sub_state: properties.get("SubState").unwrap_or(&"").to_string(),
+        restart_count: properties
+            .get("NRestarts")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0),
+        since: timestamp("StateChangeTimestamp"),
+        started_at: timestamp("ActiveEnterTimestamp"),
+        kind: ServiceKind::Systemd.into(),
+        ..Default::default()
+    })
+}
+
+/// Gets the configured systemd units as services
+/// Returns empty vector if systemd is not available
+pub fn get_systemd_services(units: &[String]) -> Vec<Service> {
+    let mut resolved: Vec<String> = Vec::new();
+    for unit in units {
+        if unit == "failed" {
+            resolved.extend(failed_units());
+        } else {
+            resolved.push(unit.clone());
+        }
+    }
+    resolved.sort();
+    resolved.dedup();
+
+    if resolved.is_empty() {
+        return vec![];
+    }
+
+    show_units(&resolved)
+        .unwrap_or_default()
+        .split("\n\n")
+        .filter_map(parse_unit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `systemctl show --timestamp=unix` for a running and a failed unit
+    const SHOW_UNIX: &str = "\
+Id=nginx.service
+ActiveState=active
+SubState=running
+NRestarts=0
+StateChangeTimestamp=@1718000000
+ActiveEnterTimestamp=@1718000000
+
+Id=backup.service
+ActiveState=failed
+SubState=failed
+NRestarts=3
+StateChangeTimestamp=@1718003600
+ActiveEnterTimestamp=
+";
+
+    #[test]
+    fn parses_units_with_unix_timestamps() {
+        let services: Vec<Service> = SHOW_UNIX.split("\n\n").filter_map(parse_unit).collect();
+        assert_eq!(services.len(), 2);
+
+        let nginx = &services[0];
+        assert_eq!(nginx.name, "nginx.service");
+        assert_eq!(
+            (nginx.status.as_str(), nginx.sub_state.as_str()),
+            ("active", "running")
+        );
+        assert_eq!(
+            (nginx.since, nginx.started_at),
+            (1_718_000_000, 1_718_000_000)
+        );
+        assert_eq!(nginx.kind(), ServiceKind::Systemd);
+
+        let backup = &services[1];
+        assert_eq!(
+            (backup.status.as_str(), backup.sub_state.as_str()),
+            ("failed", "failed")
+        );
+        assert_eq!(backup.restart_count, 3);
+        assert_eq!((backup.since, backup.started_at), (1_718_003_600, 0));
+    }
+
+    #[test]
+    fn older_systemd_timestamps_are_unknown() {
+        // Before v248 `--timestamp=unix` is rejected and dates come formatted
+        let service = parse_unit(
+            "Id=cron.service\n\
+             ActiveState=active\n\
+             SubState=running\n\
+             NRestarts=0\n\
+             StateChangeTimestamp=Mon 2024-06-10 08:00:00 UTC\n\
+             ActiveEnterTimestamp=Mon 2024-06-10 08:00:00 UTC",
+        )
+        .unwrap();
+        assert_eq!(service.status, "active");
+        assert_eq!((service.since, service.started_at), (0, 0));
+    }
+
+    #[test]
+    fn blocks_without_an_id_are_skipped() {
+        assert!(parse_unit("").is_none());
+        assert!(parse_unit("ActiveState=active\nSubState=running").is_none());
+
+        let service = parse_unit("Id=odd.service\nNRestarts=many").unwrap();
+        assert_eq!(service.status, "unknown");
+        assert_eq!(service.restart_count, 0);
+    }
+}
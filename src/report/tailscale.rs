@@ -0,0 +1,253 @@
+/// Tailscale status collection
+///
+/// Parses `tailscale status --json` every `SWARM_TAILSCALE_INTERVAL` seconds
+/// for the node's tailnet name, MagicDNS name, addresses, exit node role and
+/// key expiry, plus the peers it can see. The peers let the sentinel list
+/// tailnet machines that have no reporter installed.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::DateTime;
+use serde::Deserialize;
+
+use crate::report::config::env_secs;
+use crate::swarmreport::{TailscalePeer, TailscaleStatus};
+
+/// Latest status, None while Tailscale is missing or not answering
+pub type SharedTailscaleStatus = Arc<Mutex<Option<TailscaleStatus>>>;
+
+#[derive(Deserialize, Default)]
+struct Status {
+    #[serde(rename = "BackendState", default)]
+    backend_state: String,
+    #[serde(rename = "CurrentTailnet", default)]
+    current_tailnet: Option<Tailnet>,
+    #[serde(rename = "Self", default)]
+    self_node: Option<Node>,
+    #[serde(rename = "Peer", default)]
+    peers: Option<HashMap<String, Node>>,
+}
+
+#[derive(Deserialize, Default)]
+struct Tailnet {
+    #[serde(rename = "Name", default)]
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+struct Node {
+    #[serde(rename = "HostName", default)]
+    hostname: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    addresses: Option<Vec<String>>,
+    #[serde(rename = "OS", default)]
+    os: String,
+    #[serde(rename = "Online", default)]
+    online: bool,
+    /// The node is the exit node currently in use
+    #[serde(rename = "ExitNode", default)]
+    exit_node: bool,
+    /// The node can be used as an exit node
+    #[serde(rename = "ExitNodeOption", default)]
+    exit_node_option: bool,
+    #[serde(rename = "LastSeen", default)]
+    last_seen: Option<String>,
+    #[serde(rename = "KeyExpiry", default)]
+    key_expiry: Option<String>,
+}
+
+/// Converts an RFC 3339 time to Unix seconds; Go's zero time and missing
+/// values become 0
+fn unix_seconds(time: &Option<String>) -> i64 {
+    time.as_deref()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map_or(0, |time| time.timestamp().max(0))
+}
+
+fn dns_name(node: &Node) -> String {
+    node.dns_name.trim_end_matches('.').to_string()
+}
+
+/// Converts the CLI's JSON into the report's status
+fn parse_status(json: &str) -> Option<TailscaleStatus> {
+    let status: Status = serde_json::from_str(json).ok()?;
+    let self_node = status.self_node.unwrap_or_default();
+
+    let peers: Vec<&Node> = status
+        .peers
+        .as_ref()
+        .map(|peers| peers.values().collect())
+        .unwrap_or_default();
+
+    let mut reported_peers: Vec<TailscalePeer> = peers
+        .iter()
+        .map(|peer| TailscalePeer {
+            hostname: peer.hostname.clone(),
+            dns_name: dns_name(peer),
+            addresses: peer.addresses.clone().unwrap_or_default(),
+            os: peer.os.clone(),
+            online: peer.online,
+            offers_exit_node: peer.exit_node_option,
+            last_seen: unix_seconds(&peer.last_seen),
+            key_expiry: unix_seconds(&peer.key_expiry),
+        })
+        .collect();
+    reported_peers.sort_by(|a, b| a.dns_name.cmp(&b.dns_name));
+
+    Some(TailscaleStatus {
+        backend_state: status.backend_state,
+        tailnet: status
+            .current_tailnet
+            .map(|tailnet| tailnet.name)
+            .unwrap_or_default(),
+        dns_name: dns_name(&self_node),
+        addresses: self_node.addresses.clone().unwrap_or_default(),
+        offers_exit_node: self_node.exit_node_option,
+        exit_node: peers
+            .iter()
+            .find(|peer| peer.exit_node)
+            .map(|peer| peer.hostname.clone())
+            .unwrap_or_default(),
+        key_expiry: unix_seconds(&self_node.key_expiry),
+        peers: reported_peers,
+    })
+}
+
+async fn read_status() -> Option<TailscaleStatus> {
+    let output = tokio::process::Command::new("tailscale")
+        .args(["status", "--json"])
+        .output()
+        .await
+        .ok()?;
+    // `tailscale status` exits non-zero when logged out but still prints JSON
+    parse_status(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Starts polling Tailscale when its CLI is installed and returns the shared
+/// latest status
+pub fn spawn_tailscale_collector() -> SharedTailscaleStatus {
+    let status = SharedTailscaleStatus::default();
+    let interval = env_secs("SWARM_TAILSCALE_INTERVAL", 60);
+    if interval.is_zero() {
+        return status;
+    }
+
+    let shared = status.clone();
+    tokio::spawn(async move {
+        loop {
+            let current = read_status().await;
+            if current.is_none() && shared.lock().unwrap().is_none() {
+                // No Tailscale on this host; check again rarely in case it's installed later
+                tokio::time::sleep(interval * 10).await;
+                continue;
+            }
+            *shared.lock().unwrap() = current;
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `tailscale status --json`, trimmed to the fields that matter
+    const RUNNING: &str = r#"{
+  "Version": "1.70.0-t1234567-g89abcdef",
+  "BackendState": "Running",
+  "TailscaleIPs": ["100.64.0.10", "fd7a:115c:a1e0::a"],
+  "Self": {
+    "ID": "nAbCdEf1CNTRL",
+    "HostName": "web-1",
+    "DNSName": "web-1.tail1234.ts.net.",
+    "OS": "linux",
+    "TailscaleIPs": ["100.64.0.10", "fd7a:115c:a1e0::a"],
+    "Online": true,
+    "ExitNode": false,
+    "ExitNodeOption": true,
+    "LastSeen": "0001-01-01T00:00:00Z",
+    "KeyExpiry": "2024-12-01T10:00:00Z"
+  },
+  "Peer": {
+    "nodekey:1111": {
+      "HostName": "phone",
+      "DNSName": "phone.tail1234.ts.net.",
+      "OS": "iOS",
+      "TailscaleIPs": ["100.64.0.30"],
+      "Online": false,
+      "ExitNode": false,
+      "ExitNodeOption": false,
+      "LastSeen": "2024-06-10T08:00:00Z"
+    },
+    "nodekey:2222": {
+      "HostName": "nas",
+      "DNSName": "nas.tail1234.ts.net.",
+      "OS": "linux",
+      "TailscaleIPs": ["100.64.0.20", "fd7a:115c:a1e0::14"],
+      "Online": true,
+      "ExitNode": true,
+      "ExitNodeOption": true,
+      "LastSeen": "0001-01-01T00:00:00Z",
+      "KeyExpiry": "2025-01-15T00:00:00Z"
+    }
+  },
+  "CurrentTailnet": {
+    "Name": "example.com",
+    "MagicDNSSuffix": "tail1234.ts.net",
+    "MagicDNSEnabled": true
+  }
+}"#;
+
+    #[test]
+    fn parses_a_running_node_and_its_peers() {
+        let status = parse_status(RUNNING).unwrap();
+        assert_eq!(status.backend_state, "Running");
+        assert_eq!(status.tailnet, "example.com");
+        assert_eq!(status.dns_name, "web-1.tail1234.ts.net");
+        assert_eq!(status.addresses, ["100.64.0.10", "fd7a:115c:a1e0::a"]);
+        assert!(status.offers_exit_node);
+        assert_eq!(status.exit_node, "nas");
+        assert_eq!(status.key_expiry, 1_733_047_200);
+
+        // Sorted by DNS name
+        let names: Vec<&str> = status
+            .peers
+            .iter()
+            .map(|peer| peer.hostname.as_str())
+            .collect();
+        assert_eq!(names, ["nas", "phone"]);
+        let (nas, phone) = (&status.peers[0], &status.peers[1]);
+        assert!(nas.online && nas.offers_exit_node);
+        // Go's zero time means "never" and becomes 0
+        assert_eq!(nas.last_seen, 0);
+        assert_eq!(nas.key_expiry, 1_736_899_200);
+        assert_eq!((phone.online, phone.os.as_str()), (false, "iOS"));
+        assert_eq!(phone.last_seen, 1_718_006_400);
+        assert_eq!(phone.key_expiry, 0);
+    }
+
+    #[test]
+    fn parses_a_logged_out_node() {
+        let status = parse_status(
+            r#"{"Version": "1.70.0", "BackendState": "NeedsLogin", "Self": null, "Peer": null, "CurrentTailnet": null}"#,
+        )
+        .unwrap();
+        assert_eq!(status.backend_state, "NeedsLogin");
+        assert_eq!(
+            (status.tailnet.as_str(), status.dns_name.as_str()),
+            ("", "")
+        );
+        assert!(status.addresses.is_empty() && status.peers.is_empty());
+        assert_eq!(status.exit_node, "");
+    }
+
+    #[test]
+    fn rejects_output_that_is_not_json() {
+        assert!(parse_status("").is_none());
+        assert!(parse_status("failed to connect to local tailscaled").is_none());
+    }
+}
@@ -0,0 +1,437 @@
+/// Container image update detection
+///
+/// Periodically compares the digest of every running container image with the
+/// digest its registry currently serves for the same tag. Results are cached
+/// in shared state and picked up by the collector when building reports.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT, AUTHORIZATION, HeaderMap, RETRY_AFTER, WWW_AUTHENTICATE};
+
+use crate::report::config::{env_list, env_secs};
+use crate::report::containers::ContainerRuntime;
+
+type CheckResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const DOCKER_HUB: &str = "registry-1.docker.io";
+
+/// Manifest media types we accept, so multi-arch images resolve to the index digest
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json, \
+    application/vnd.oci.image.manifest.v1+json";
+
+/// Seconds to pause a registry that rate-limited us without saying for how long
+const DEFAULT_RATE_LIMIT_BACKOFF: u64 = 3600;
+
+/// Latest update status per image reference, keyed as reported by the runtime
+pub type SharedImageUpdates = Arc<Mutex<HashMap<String, bool>>>;
+
+/// A parsed image reference such as "ghcr.io/org/app:1.2"
+struct ImageRef {
+    registry: String,
+    repository: String,
+    tag: String,
+}
+
+impl ImageRef {
+    /// Parses an image reference, returning None for digest-pinned images
+    /// since those can never drift from what they point at
+    fn parse(image: &str) -> Option<Self> {
+        if image.contains('@') {
+            return None;
+        }
+
+        let (registry, remainder) = match image.split_once('/') {
+            Some((first, rest))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                (first.to_string(), rest.to_string())
+            }
+            _ => (DOCKER_HUB.to_string(), image.to_string()),
+        };
+        let registry = match registry.as_str() {
+            "docker.io" | "index.docker.io" => DOCKER_HUB.to_string(),
+            _ => registry,
+        };
+
+        let (repository, tag) = match remainder.rsplit_once(':') {
+            Some((repository, tag)) => (repository.to_string(), tag.to_string()),
+            None => (remainder, "latest".to_string()),
+        };
+
+        // Official Docker Hub images live under the "library" namespace
+        let repository = if registry == DOCKER_HUB && !repository.contains('/') {
+            format!("library/{repository}")
+        } else {
+            repository
+        };
+
+        Some(Self {
+            registry,
+            repository,
+            tag,
+        })
+    }
+}
+
+/// Reads the base64 "user:password" credentials stored by `docker login`
+fn registry_credentials(registry: &str) -> Option<String> {
+    let config_dir = std::env::var("DOCKER_CONFIG")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".docker")))
+        .ok()?;
+    let config = std::fs::read_to_string(config_dir.join("config.json")).ok()?;
+    let config: serde_json::Value = serde_json::from_str(&config).ok()?;
+
+    let host = if registry == DOCKER_HUB {
+        "index.docker.io"
+    } else {
+        registry
+    };
+
+    config
+        .get("auths")?
+        .as_object()?
+        .iter()
+        .find(|(key, _)| {
+            key.trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .split('/')
+                .next()
+                == Some(host)
+        })
+        .and_then(|(_, entry)| entry.get("auth")?.as_str().map(str::to_string))
+}
+
+/// Parses the key="value" parameters of a WWW-Authenticate challenge
+fn parse_challenge(params: &str) -> HashMap<String, String> {
+    params
+        .split(',')
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Talks to registries using the Docker Registry HTTP API v2
+struct RegistryClient {
+    http: reqwest::Client,
+    /// Registries reached over plain HTTP, e.g. a local `registry:2`
+    insecure: Vec<String>,
+    /// Registries that rate-limited us, with the time we may ask again
+    paused_until: HashMap<String, Instant>,
+}
+
+impl RegistryClient {
+    fn new() -> CheckResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent(concat!("swarmreport/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        Ok(Self {
+            http,
+            insecure: env_list("SWARM_INSECURE_REGISTRIES"),
+            paused_until: HashMap::new(),
+        })
+    }
+
+    fn scheme(&self, registry: &str) -> &'static str {
+        let local = registry.starts_with("localhost") || registry.starts_with("127.");
+        if local || self.insecure.iter().any(|r| r == registry) {
+            "http"
+        } else {
+            "https"
+        }
+    }
+
+    /// Checks whether a running image, whose local repo digests are `local`,
+    /// is behind its tag in the registry. Returns None when the answer is
+    /// unknown, so the previous result is kept.
+    async fn check(&mut self, image: &str, local: &[String]) -> Option<bool> {
+        let reference = ImageRef::parse(image)?;
+
+        if let Some(until) = self.paused_until.get(&reference.registry) {
+            if Instant::now() < *until {
+                return None;
+            }
+            self.paused_until.remove(&reference.registry);
+        }
+
+        // Locally built images have no registry digest to compare against
+        if local.is_empty() {
+            return None;
+        }
+
+        match self.remote_digest(&reference).await {
+            Ok(remote) => Some(!local.contains(&remote)),
+            Err(e) => {
+                eprintln!("Update check for {image} failed: {e}");
+                None
+            }
+        }
+    }
+
+    /// Resolves the digest the registry currently serves for an image tag.
+    /// Uses HEAD requests, which Docker Hub does not count against pull limits.
+    async fn remote_digest(&mut self, image: &ImageRef) -> CheckResult<String> {
+        let url = format!(
+            "{}://{}/v2/{}/manifests/{}",
+            self.scheme(&image.registry),
+            image.registry,
+            image.repository,
+            image.tag
+        );
+
+        let mut response = self
+            .http
+            .head(&url)
+            .header(ACCEPT, MANIFEST_TYPES)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let authorization = self.authorize(image, response.headers()).await?;
+            response = self
+                .http
+                .head(&url)
+                .header(ACCEPT, MANIFEST_TYPES)
+                .header(AUTHORIZATION, authorization)
+                .send()
+                .await?;
+        }
+
+        self.note_rate_limit(&image.registry, &response);
+
+        if !response.status().is_success() {
+            return Err(format!("registry returned {}", response.status()).into());
+        }
+
+        response
+            .headers()
+            .get("docker-content-digest")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| "registry response has no Docker-Content-Digest".into())
+    }
+
+    /// Answers a 401 challenge with either a bearer token or basic credentials
+    async fn authorize(&self, image: &ImageRef, headers: &HeaderMap) -> CheckResult<String> {
+        let challenge = headers
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let credentials = registry_credentials(&image.registry);
+
+        let Some(params) = challenge.strip_prefix("Bearer ") else {
+            return credentials
+                .map(|c| format!("Basic {c}"))
+                .ok_or_else(|| "registry requires credentials".into());
+        };
+
+        let params = parse_challenge(params);
+        let realm = params
+            .get("realm")
+            .ok_or("bearer challenge without realm")?;
+
+        let mut request = self
+            .http
+            .get(realm.as_str())
+            .query(&[("scope", format!("repository:{}:pull", image.repository))]);
+        if let Some(service) = params.get("service") {
+            request = request.query(&[("service", service)]);
+        }
+        if let Some(credentials) = credentials {
+            request = request.header(AUTHORIZATION, format!("Basic {credentials}"));
+        }
+
+        let body: serde_json::Value = request.send().await?.error_for_status()?.json().await?;
+        let token = body
+            .get("token")
+            .or_else(|| body.get("access_token"))
+            .and_then(|token| token.as_str())
+            .ok_or("token response without token")?;
+
+        Ok(format!("Bearer {token}"))
+    }
+
+    /// Pauses a registry when it answers 429 or reports an exhausted quota
+    fn note_rate_limit(&mut self, registry: &str, response: &reqwest::Response) {
+        let headers = response.headers();
+
+        let wait_secs = if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            headers
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()?.trim().parse().ok())
+                .unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF)
+        } else {
+            // Docker Hub style quota header, e.g. "ratelimit-remaining: 0;w=21600"
+            let Some(remaining) = headers
+                .get("ratelimit-remaining")
+                .and_then(|value| value.to_str().ok())
+            else {
+                return;
+            };
+            let mut parts = remaining.split(';');
+            if parts.next().and_then(|n| n.trim().parse::<u64>().ok()) != Some(0) {
+                return;
+            }
+            parts
+                .find_map(|part| part.trim().strip_prefix("w=")?.parse().ok())
+                .unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF)
+        };
+
+        eprintln!("Registry {registry} rate-limited update checks, pausing for {wait_secs}s");
+        self.paused_until.insert(
+            registry.to_string(),
+            Instant::now() + Duration::from_secs(wait_secs),
+        );
+    }
+}
+
+/// Starts the background update checker and returns its shared results.
+/// Setting `SWARM_UPDATE_CHECK_INTERVAL=0` disables checking entirely.
+pub fn spawn_update_checker(runtimes: Vec<ContainerRuntime>) -> SharedImageUpdates {
+    let updates = SharedImageUpdates::default();
+    let interval = env_secs("SWARM_UPDATE_CHECK_INTERVAL", 3600);
+    if interval.is_zero() {
+        return updates;
+    }
+
+    let mut registry = match RegistryClient::new() {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("Image update checks disabled: {e}");
+            return updates;
+        }
+    };
+
+    let shared = updates.clone();
+    tokio::spawn(async move {
+        loop {
+            let mut images: Vec<String> = Vec::new();
+
+            for runtime in &runtimes {
+                // The runtime is queried through its CLI or socket, which blocks
+                let containers = {
+                    let runtime = runtime.clone();
+                    tokio::task::spawn_blocking(move || runtime.containers())
+                        .await
+                        .unwrap_or_default()
+                };
+                for container in containers {
+                    if images.contains(&container.image) {
+                        continue;
+                    }
+                    let local = {
+                        let runtime = runtime.clone();
+                        let image = container.image.clone();
+                        tokio::task::spawn_blocking(move || runtime.image_digests(&image))
+                            .await
+                            .unwrap_or_default()
+                    };
+                    if let Some(needs_update) = registry.check(&container.image, &local).await {
+                        shared
+                            .lock()
+                            .unwrap()
+                            .insert(container.image.clone(), needs_update);
+                    }
+                    images.push(container.image);
+                }
+            }
+
+            // Forget images that are no longer running
+            shared
+                .lock()
+                .unwrap()
+                .retain(|image, _| images.contains(image));
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves the manifest digest of `team/app:1.0` like a local `registry:2`,
+    /// answering with whatever `digest` currently holds
+    async fn fake_registry(digest: Arc<Mutex<String>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 4096];
+                let read = socket.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                let response = if request.starts_with("HEAD /v2/team/app/manifests/1.0 ") {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nDocker-Content-Digest: {}\r\n\
+                         Content-Length: 0\r\nConnection: close\r\n\r\n",
+                        digest.lock().unwrap()
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn needs_update_flips_when_the_tag_moves() {
+        let digest = Arc::new(Mutex::new("sha256:aaaa".to_string()));
+        let registry_address = fake_registry(digest.clone()).await;
+        let image = format!("{registry_address}/team/app:1.0");
+        let local = vec!["sha256:aaaa".to_string()];
+        let mut registry = RegistryClient::new().unwrap();
+
+        assert_eq!(registry.check(&image, &local).await, Some(false));
+
+        *digest.lock().unwrap() = "sha256:bbbb".to_string();
+        assert_eq!(registry.check(&image, &local).await, Some(true));
+    }
+
+    #[tokio::test]
+    async fn unknown_when_the_registry_has_no_such_tag() {
+        let registry_address = fake_registry(Arc::default()).await;
+        let image = format!("{registry_address}/team/app:2.0");
+        let mut registry = RegistryClient::new().unwrap();
+
+        assert_eq!(
+            registry.check(&image, &["sha256:aaaa".to_string()]).await,
+            None
+        );
+    }
+
+    #[test]
+    fn parses_image_references() {
+        assert!(ImageRef::parse("nginx@sha256:aaaa").is_none());
+
+        let reference = ImageRef::parse("nginx").unwrap();
+        assert_eq!(reference.registry, DOCKER_HUB);
+        assert_eq!(reference.repository, "library/nginx");
+        assert_eq!(reference.tag, "latest");
+
+        let reference = ImageRef::parse("localhost:5000/team/app:1.0").unwrap();
+        assert_eq!(reference.registry, "localhost:5000");
+        assert_eq!(reference.repository, "team/app");
+        assert_eq!(reference.tag, "1.0");
+    }
+}
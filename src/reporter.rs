@@ -8,18 +8,34 @@ pub mod swarmreport {
     tonic::include_proto!("swarmreport");
 }
 
-use report::{get_swarm_report, send_system_report};
+use std::sync::{Arc, Mutex};
+
+use report::{Delivery, get_swarm_report, send_system_report, system::Collector};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("SwarmReport Reporter starting...");
 
+    let collector = Arc::new(Mutex::new(Collector::new()));
+    let mut delivery = Delivery::from_env();
+    // Sentinel whose swarm report was last fetched; the report lists every
+    // node, so it is only fetched after (re)connecting
+    let mut announced: Option<String> = None;
+
     loop {
-        // Send reports every 500ms
+        // Collect reports every 500ms; delivery backs off while a sentinel
+        // is unreachable
         tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-        // Continue the loop even if operations fail
-        let _ = send_system_report().await;
-        let _ = get_swarm_report().await;
+        // Continue the loop even if operations fail; undelivered reports
+        // stay buffered and are replayed on the next successful send
+        let _ = send_system_report(&collector, &mut delivery).await;
+        let connected = delivery.connected_sentinel().map(str::to_string);
+        if connected != announced
+            && let Some(sentinel) = &connected
+        {
+            let _ = get_swarm_report(sentinel).await;
+        }
+        announced = connected;
     }
 }
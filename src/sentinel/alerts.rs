@@ -0,0 +1,505 @@
+/// Alerting for SwarmReport Sentinel
+///
+/// Evaluates alert rules against the latest report of every node. Firing
+/// alerts are shown in the TUI and dashboard, and when `SWARM_ALERT_WEBHOOK`
+/// is set every transition (firing, resolved) is posted to it as JSON.
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::ha::HaStatus;
+use super::state::StateHandle;
+use super::types::{ReportEntry, ReportStream};
+use crate::swarmreport::{Certificate, CheckStatus, Temperature};
+
+/// A rule violation on one node
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub node_id: String,
+    pub hostname: String,
+    /// Rule that fired: "temperature", "check", "probe", "certificate" or "clock"
+    pub rule: String,
+    /// What the rule fired on, e.g. the sensor label
+    pub subject: String,
+    /// "warn" or "crit"
+    pub level: String,
+    pub message: String,
+}
+
+impl Alert {
+    /// Identifies the alert across evaluations
+    fn key(&self) -> String {
+        format!("{}/{}/{}", self.node_id, self.rule, self.subject)
+    }
+}
+
+/// Reads a floating point setting, falling back to `default` when unset or invalid
+fn env_f64(name: &str, default: f64) -> f64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Fires when a sensor gets within `warn_margin` degrees of its critical
+/// temperature, and critically once it reaches it
+pub struct TemperatureRule {
+    warn_margin: f64,
+    /// Used for sensors that don't report a critical threshold
+    default_critical: f64,
+}
+
+impl TemperatureRule {
+    pub fn from_env() -> Self {
+        Self {
+            warn_margin: env_f64("SWARM_TEMP_WARN_MARGIN", 10.0),
+            default_critical: env_f64("SWARM_TEMP_CRITICAL", 90.0),
+        }
+    }
+
+    pub fn critical(&self, temperature: &Temperature) -> f64 {
+        if temperature.critical_celsius > 0.0 {
+            temperature.critical_celsius
+        } else {
+            self.default_critical
+        }
+    }
+
+    /// Classifies a reading as "ok", "warn" or "crit"
+    pub fn level(&self, temperature: &Temperature) -> &'static str {
+        let critical = self.critical(temperature);
+        if temperature.current_celsius >= critical {
+            "crit"
+        } else if temperature.current_celsius >= critical - self.warn_margin {
+            "warn"
+        } else {
+            "ok"
+        }
+    }
+}
+
+/// Fires when a certificate expires within `warn_days`, and critically
+/// within `critical_days` or once expired
+pub struct CertificateRule {
+    warn_days: i64,
+    critical_days: i64,
+}
+
+impl CertificateRule {
+    pub fn from_env() -> Self {
+        Self {
+            warn_days: env_f64("SWARM_CERT_WARN_DAYS", 21.0) as i64,
+            critical_days: env_f64("SWARM_CERT_CRITICAL_DAYS", 7.0) as i64,
+        }
+    }
+
+    /// Whole days until the certificate expires, negative once it has
+    pub fn days_remaining(certificate: &Certificate, now: i64) -> i64 {
+        (certificate.not_after - now).div_euclid(86_400)
+    }
+
+    /// Classifies a certificate as "ok", "warn" or "crit"; certificates that
+    /// could not be read are warnings
+    pub fn level(&self, certificate: &Certificate, now: i64) -> &'static str {
+        if !certificate.error.is_empty() {
+            return "warn";
+        }
+        let days = Self::days_remaining(certificate, now);
+        if days < self.critical_days {
+            "crit"
+        } else if days < self.warn_days {
+            "warn"
+        } else {
+            "ok"
+        }
+    }
+}
+
+/// Fires when a node's clock is more than `max_skew_ms` away from the sentinel's
+pub struct ClockRule {
+    max_skew_ms: i64,
+}
+
+impl ClockRule {
+    pub fn from_env() -> Self {
+        Self {
+            max_skew_ms: (env_f64("SWARM_CLOCK_SKEW_MAX", 5.0) * 1000.0) as i64,
+        }
+    }
+
+    pub fn drifting(&self, stream: &ReportStream) -> bool {
+        stream
+            .clock_skew_ms()
+            .is_some_and(|skew| skew.abs() > self.max_skew_ms)
+    }
+}
+
+/// All alert rules, configured from the environment
+pub struct AlertRules {
+    pub temperature: TemperatureRule,
+    pub certificate: CertificateRule,
+    pub clock: ClockRule,
+}
+
+impl AlertRules {
+    pub fn from_env() -> Self {
+        Self {
+            temperature: TemperatureRule::from_env(),
+            certificate: CertificateRule::from_env(),
+            clock: ClockRule::from_env(),
+        }
+    }
+
+    /// Returns the alerts firing for one node
+    pub fn evaluate(&self, entry: &ReportEntry) -> Vec<Alert> {
+        let report = &entry.report;
+        let alert = |rule: &str, subject: &str, level: &str, message: String| Alert {
+            node_id: report.node_id.clone(),
+            hostname: report.hostname.clone(),
+            rule: rule.to_string(),
+            subject: subject.to_string(),
+            level: level.to_string(),
+            message,
+        };
+
+        let mut alerts = Vec::new();
+
+        for temperature in &report.temperatures {
+            let level = self.temperature.level(temperature);
+            if level != "ok" {
+                alerts.push(alert(
+                    "temperature",
+                    &temperature.label,
+                    level,
+                    format!(
+                        "{} at {:.0}°C (critical {:.0}°C)",
+                        temperature.label,
+                        temperature.current_celsius,
+                        self.temperature.critical(temperature)
+                    ),
+                ));
+            }
+        }
+
+        for check in &report.checks {
+            let level = match check.status() {
+                CheckStatus::Critical => "crit",
+                CheckStatus::Warning => "warn",
+                CheckStatus::Ok | CheckStatus::Unknown => continue,
+            };
+            alerts.push(alert(
+                "check",
+                &check.name,
+                level,
+                format!("{}: {}", check.name, check.message),
+            ));
+        }
+
+        for service in &report.services {
+            for probe in &service.probes {
+                let level = match probe.status() {
+                    CheckStatus::Critical => "crit",
+                    CheckStatus::Warning => "warn",
+                    CheckStatus::Ok | CheckStatus::Unknown => continue,
+                };
+                alerts.push(alert(
+                    "probe",
+                    &format!("{}/{}", service.name, probe.name),
+                    level,
+                    format!("{} probe {}: {}", service.name, probe.name, probe.message),
+                ));
+            }
+        }
+
+        if self.clock.drifting(&entry.stream)
+            && let Some(skew) = entry.stream.clock_skew_ms()
+        {
+            let direction = if skew > 0 { "ahead of" } else { "behind" };
+            alerts.push(alert(
+                "clock",
+                "skew",
+                "warn",
+                format!(
+                    "clock is {:.1}s {direction} the sentinel",
+                    skew.abs() as f64 / 1000.0
+                ),
+            ));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        for certificate in &report.certificates {
+            let level = self.certificate.level(certificate, now);
+            if level == "ok" {
+                continue;
+            }
+            let message = if certificate.error.is_empty() {
+                let days = CertificateRule::days_remaining(certificate, now);
+                if days < 0 {
+                    format!("{} expired {} days ago", certificate.source, -days)
+                } else {
+                    format!("{} expires in {} days", certificate.source, days)
+                }
+            } else {
+                format!("{}: {}", certificate.source, certificate.error)
+            };
+            alerts.push(alert("certificate", &certificate.source, level, message));
+        }
+
+        alerts
+    }
+}
+
+/// Posts a transition to the webhook, ignoring delivery failures
+async fn notify(client: &reqwest::Client, webhook: &str, status: &str, alert: &Alert) {
+    let _ = client
+        .post(webhook)
+        .json(&serde_json::json!({ "status": status, "alert": alert }))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await;
+}
+
+/// Evaluates alerts every 5 seconds and posts transitions to
+/// `SWARM_ALERT_WEBHOOK`; does nothing when it is unset. In a high
+/// availability pair both sentinels track alerts but only the leader posts,
+/// so a standby that takes over doesn't repeat alerts that already fired.
+pub async fn run_alert_notifier(state: StateHandle) {
+    let Ok(webhook) = std::env::var("SWARM_ALERT_WEBHOOK") else {
+        return;
+    };
+    let client = reqwest::Client::new();
+    let mut firing: HashMap<String, Alert> = HashMap::new();
+
+    loop {
+        let (current, leader): (HashMap<String, Alert>, bool) = {
+            let app = state.snapshot();
+            let current = app
+                .get_alerts()
+                .into_iter()
+                .map(|alert| (alert.key(), alert))
+                .collect();
+            (current, app.ha.as_ref().is_none_or(HaStatus::is_leader))
+        };
+        if !leader {
+            firing = current;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+
+        for (key, alert) in &current {
+            if firing
+                .get(key)
+                .is_none_or(|previous| previous.level != alert.level)
+            {
+                notify(&client, &webhook, "firing", alert).await;
+            }
+        }
+        for (key, alert) in &firing {
+            if !current.contains_key(key) {
+                notify(&client, &webhook, "resolved", alert).await;
+            }
+        }
+        firing = current;
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::swarmreport::{CheckResult, Service, SystemReport};
+
+    fn rules() -> AlertRules {
+        AlertRules {
+            temperature: TemperatureRule {
+                warn_margin: 10.0,
+                default_critical: 90.0,
+            },
+            certificate: CertificateRule {
+                warn_days: 21,
+                critical_days: 7,
+            },
+            clock: ClockRule { max_skew_ms: 5_000 },
+        }
+    }
+
+    fn entry(report: SystemReport) -> ReportEntry {
+        ReportEntry::received(SystemReport {
+            node_id: "node".to_string(),
+            hostname: "host".to_string(),
+            ..report
+        })
+    }
+
+    fn levels(alerts: &[Alert], rule: &str) -> Vec<(String, String)> {
+        alerts
+            .iter()
+            .filter(|alert| alert.rule == rule)
+            .map(|alert| (alert.subject.clone(), alert.level.clone()))
+            .collect()
+    }
+
+    fn check(name: &str, status: CheckStatus) -> CheckResult {
+        CheckResult {
+            name: name.to_string(),
+            status: status.into(),
+            message: "failed".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn certificate(source: &str, days: i64) -> Certificate {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        Certificate {
+            source: source.to_string(),
+            // Half a day of slack so the whole days don't depend on timing
+            not_after: now + days * 86_400 + 43_200,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn temperatures_near_and_at_critical() {
+        let temperature = |label: &str, current: f64, critical: f64| Temperature {
+            label: label.to_string(),
+            current_celsius: current,
+            critical_celsius: critical,
+            ..Default::default()
+        };
+        let alerts = rules().evaluate(&entry(SystemReport {
+            temperatures: vec![
+                temperature("cool", 50.0, 90.0),
+                temperature("package", 84.0, 90.0),
+                // Without a critical threshold the default of 90°C applies
+                temperature("nvme", 95.0, 0.0),
+            ],
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            levels(&alerts, "temperature"),
+            [
+                ("package".to_string(), "warn".to_string()),
+                ("nvme".to_string(), "crit".to_string()),
+            ]
+        );
+        assert_eq!(alerts[1].message, "nvme at 95°C (critical 90°C)");
+    }
+
+    #[test]
+    fn failing_plugin_checks() {
+        let alerts = rules().evaluate(&entry(SystemReport {
+            checks: vec![
+                check("backup", CheckStatus::Warning),
+                check("ups", CheckStatus::Ok),
+                check("raid", CheckStatus::Critical),
+                check("smart", CheckStatus::Unknown),
+            ],
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            levels(&alerts, "check"),
+            [
+                ("backup".to_string(), "warn".to_string()),
+                ("raid".to_string(), "crit".to_string()),
+            ]
+        );
+        assert_eq!(alerts[0].message, "backup: failed");
+    }
+
+    #[test]
+    fn failing_service_probes() {
+        let alerts = rules().evaluate(&entry(SystemReport {
+            services: vec![Service {
+                name: "postgres".to_string(),
+                probes: vec![
+                    check("tcp", CheckStatus::Critical),
+                    check("http", CheckStatus::Ok),
+                    check("dns", CheckStatus::Warning),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            levels(&alerts, "probe"),
+            [
+                ("postgres/tcp".to_string(), "crit".to_string()),
+                ("postgres/dns".to_string(), "warn".to_string()),
+            ]
+        );
+        assert_eq!(alerts[0].message, "postgres probe tcp: failed");
+    }
+
+    #[test]
+    fn certificates_warn_then_turn_critical() {
+        let mut unreadable = certificate("broken.example", 300);
+        unreadable.error = "connection refused".to_string();
+        let alerts = rules().evaluate(&entry(SystemReport {
+            certificates: vec![
+                certificate("long.example", 300),
+                certificate("soon.example", 14),
+                certificate("sooner.example", 5),
+                certificate("expired.example", -3),
+                unreadable,
+            ],
+            ..Default::default()
+        }));
+
+        assert_eq!(
+            levels(&alerts, "certificate"),
+            [
+                ("soon.example".to_string(), "warn".to_string()),
+                ("sooner.example".to_string(), "crit".to_string()),
+                ("expired.example".to_string(), "crit".to_string()),
+                ("broken.example".to_string(), "warn".to_string()),
+            ]
+        );
+        assert_eq!(alerts[1].message, "sooner.example expires in 5 days");
+        assert_eq!(alerts[2].message, "expired.example expired 3 days ago");
+        assert_eq!(alerts[3].message, "broken.example: connection refused");
+    }
+
+    /// An entry whose reports were all sent `skew_ms` from when they arrived
+    fn skewed(skew_ms: i64) -> ReportEntry {
+        let mut entry = entry(SystemReport::default());
+        for sequence in 1..=3 {
+            let report = SystemReport {
+                reporter_started_at: 1,
+                sequence,
+                sent_at: 1_000_000 + skew_ms,
+                ..Default::default()
+            };
+            entry.stream.observe(&report, 1_000_000);
+        }
+        entry
+    }
+
+    #[test]
+    fn clock_skew_beyond_the_maximum() {
+        assert_eq!(
+            rules().evaluate(&entry(SystemReport::default())),
+            Vec::new()
+        );
+        assert_eq!(rules().evaluate(&skewed(3_000)), Vec::new());
+        assert_eq!(rules().evaluate(&skewed(-5_000)), Vec::new());
+
+        let ahead = rules().evaluate(&skewed(7_000));
+        assert_eq!(
+            levels(&ahead, "clock"),
+            [("skew".to_string(), "warn".to_string())]
+        );
+        assert_eq!(ahead[0].message, "clock is 7.0s ahead of the sentinel");
+
+        let behind = rules().evaluate(&skewed(-12_500));
+        assert_eq!(behind[0].message, "clock is 12.5s behind the sentinel");
+    }
+}
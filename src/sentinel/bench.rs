@@ -0,0 +1,213 @@
+/// State actor benchmark, run with `sentinel bench [nodes] [seconds] [interval_ms]`
+///
+/// Simulates `nodes` reporters (5000 by default) each sending a report every
+/// `interval_ms` (1000) for `seconds` (10), while readers take snapshots and
+/// build the web API's client list the way the dashboard does, plus one slow
+/// reader that keeps each snapshot for a second like a sluggish terminal.
+/// Prints throughput, how long events waited for the actor, how long senders
+/// waited for room in the queue and how long readers took.
+use std::time::{Duration, Instant};
+
+use super::state::{StateEvent, StateHandle, spawn_state_actor};
+use super::types::{App, ReportEntry};
+use crate::swarmreport::{
+    CpuInfo, DiskInfo, MemoryInfo, ProcessInfo, Service, ServiceKind, SystemReport,
+};
+
+const READERS: usize = 4;
+
+/// A report about the size of a real one, with disks, services and processes
+fn simulated_report(node: usize, sequence: u64) -> SystemReport {
+    let hostname = format!("bench-{node:05}");
+    SystemReport {
+        hostname: hostname.clone(),
+        node_id: format!("bench-node-{node:05}"),
+        ip_address: format!("10.{}.{}.{}", node / 65536, (node / 256) % 256, node % 256),
+        cpu_usage: format!("{:.1}%", (node as u64 + sequence) % 100),
+        memory_usage: "7.0 GiB / 16.0 GiB".to_string(),
+        disk_usage: "600.00 GiB / 1500.00 GiB".to_string(),
+        sequence,
+        collected_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64),
+        cpu: Some(CpuInfo {
+            brand: "Simulated CPU".to_string(),
+            physical_cores: 8,
+            core_usage: vec![12.5; 8],
+            ..Default::default()
+        }),
+        memory: Some(MemoryInfo {
+            total_bytes: 16 << 30,
+            used_bytes: 7 << 30,
+            ..Default::default()
+        }),
+        disks: (0..3)
+            .map(|i| DiskInfo {
+                mount_point: format!("/mnt/disk{i}"),
+                total_bytes: 500 << 30,
+                used_bytes: 200 << 30,
+                ..Default::default()
+            })
+            .collect(),
+        services: (0..10)
+            .map(|i| Service {
+                name: format!("service-{i}"),
+                status: "running".to_string(),
+                kind: ServiceKind::Container.into(),
+                ..Default::default()
+            })
+            .collect(),
+        top_cpu_processes: (0..5)
+            .map(|i| ProcessInfo {
+                pid: i,
+                name: format!("process-{i}"),
+                cpu_percent: 1.0,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Sends one node's reports until `until`, returning how long each send waited
+async fn simulate_node(
+    state: StateHandle,
+    node: usize,
+    nodes: usize,
+    interval: Duration,
+    until: Instant,
+) -> Vec<Duration> {
+    let mut waits = Vec::new();
+    // Spread the nodes over the interval like a real fleet
+    tokio::time::sleep(interval.mul_f64(node as f64 / nodes as f64)).await;
+    let mut ticker = tokio::time::interval(interval);
+
+    let mut sequence = 0;
+    while Instant::now() < until {
+        ticker.tick().await;
+        sequence += 1;
+        let entry = ReportEntry::received(simulated_report(node, sequence));
+        let started = Instant::now();
+        state.send(StateEvent::Report(entry)).await;
+        waits.push(started.elapsed());
+    }
+    waits
+}
+
+/// Takes snapshots and builds the web client list until `until`, returning
+/// how long each took
+async fn read_snapshots(
+    state: StateHandle,
+    hold: Duration,
+    until: Instant,
+) -> (Vec<Duration>, Vec<Duration>) {
+    let mut snapshots = Vec::new();
+    let mut clients = Vec::new();
+    while Instant::now() < until {
+        let started = Instant::now();
+        let app = state.snapshot();
+        snapshots.push(started.elapsed());
+
+        let started = Instant::now();
+        std::hint::black_box(app.get_web_clients());
+        clients.push(started.elapsed());
+
+        // Keeping a snapshot doesn't stop the actor from publishing newer ones
+        tokio::time::sleep(hold).await;
+        drop(app);
+    }
+    (snapshots, clients)
+}
+
+fn argument<T: std::str::FromStr>(args: &[String], index: usize, default: T) -> T {
+    args.get(index)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Formats the median, 99th percentile and maximum of `samples`
+fn percentiles(mut samples: Vec<Duration>) -> String {
+    if samples.is_empty() {
+        return "no samples".to_string();
+    }
+    samples.sort();
+    let at = |q: f64| samples[((samples.len() - 1) as f64 * q) as usize];
+    format!(
+        "p50 {:?}, p99 {:?}, max {:?}",
+        at(0.5),
+        at(0.99),
+        samples[samples.len() - 1]
+    )
+}
+
+/// Runs the benchmark with the arguments following `bench`
+pub async fn run_bench(args: &[String]) {
+    let nodes: usize = argument(args, 0, 5000);
+    let seconds: u64 = argument(args, 1, 10);
+    let interval = Duration::from_millis(argument(args, 2, 1000).max(1));
+
+    println!(
+        "Simulating {nodes} nodes reporting every {interval:?} for {seconds}s with {READERS} readers"
+    );
+    let state = spawn_state_actor(App::new());
+    let started = Instant::now();
+    let until = started + Duration::from_secs(seconds);
+
+    let senders: Vec<_> = (0..nodes)
+        .map(|node| tokio::spawn(simulate_node(state.clone(), node, nodes, interval, until)))
+        .collect();
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| Duration::from_millis(100))
+        .chain([Duration::from_secs(1)])
+        .map(|hold| tokio::spawn(read_snapshots(state.clone(), hold, until)))
+        .collect();
+
+    let mut send_waits = Vec::new();
+    for sender in senders {
+        send_waits.extend(sender.await.unwrap_or_default());
+    }
+    let mut snapshot_reads = Vec::new();
+    let mut client_lists = Vec::new();
+    for reader in readers {
+        let (snapshots, clients) = reader.await.unwrap_or_default();
+        snapshot_reads.extend(snapshots);
+        client_lists.extend(clients);
+    }
+    let sent = send_waits.len() as u64;
+
+    // Let the actor apply what is still queued and publish it
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let app = loop {
+        let app = state.snapshot();
+        if app.metrics.events_applied >= sent || Instant::now() >= deadline {
+            break app;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    let elapsed = started.elapsed();
+    let metrics = &app.metrics;
+
+    println!(
+        "Reports: {sent} sent, {} applied, {} nodes in the state ({:.0} reports/s)",
+        metrics.events_applied,
+        app.reports.len(),
+        metrics.events_applied as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "Queue: up to {} of {} events waiting, senders waited {} times: {}",
+        metrics.max_queued,
+        metrics.queue_capacity,
+        metrics.senders_blocked,
+        percentiles(send_waits)
+    );
+    println!(
+        "Apply lag: average {:.2}ms, max {:.2}ms",
+        metrics.avg_lag_ms, metrics.max_lag_ms
+    );
+    println!(
+        "Snapshots: {} published, last built in {}µs",
+        metrics.snapshots_published, metrics.snapshot_build_us
+    );
+    println!("Taking a snapshot: {}", percentiles(snapshot_reads));
+    println!("Building the client list: {}", percentiles(client_lists));
+}
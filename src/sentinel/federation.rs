@@ -0,0 +1,198 @@
+/// Sentinel federation
+///
+/// A parent sentinel polls the child sentinels in `SWARM_FEDERATION_CHILDREN`
+/// every `SWARM_FEDERATION_INTERVAL` seconds for their nodes' latest reports
+/// and shows them next to its own, labelled with the child's site. Entries
+/// are `[site=]address`; without a label the child's own `SWARM_SITE` is
+/// used, or its address. A child that stops answering is marked unreachable
+/// and its nodes age out like nodes that stop reporting.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tonic::transport::Endpoint;
+
+use super::state::{StateEvent, StateHandle};
+use super::types::{App, ReportEntry};
+use crate::swarmreport::swarm_report_service_client::SwarmReportServiceClient;
+use crate::swarmreport::{SwarmReportRequest, SwarmReportResponse};
+
+/// Time allowed for connecting to a child and for its answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest snapshot accepted from a child
+const MAX_SNAPSHOT_BYTES: usize = 64 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildState {
+    /// Not polled yet
+    Connecting,
+    /// The last poll succeeded
+    Connected,
+    /// The last poll failed
+    Unreachable,
+}
+
+impl ChildState {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChildState::Connecting => "connecting",
+            ChildState::Connected => "connected",
+            ChildState::Unreachable => "unreachable",
+        }
+    }
+}
+
+/// A child sentinel and the outcome of its last poll
+#[derive(Clone, Debug)]
+pub struct ChildSentinel {
+    /// Label for nodes that report to the child directly
+    pub site: String,
+    pub address: String,
+    pub state: ChildState,
+    /// Last successful poll, Unix seconds
+    pub last_contact: u64,
+    pub last_error: String,
+    /// Site label given in the configuration, which wins over the child's own
+    configured_site: Option<String>,
+}
+
+impl ChildSentinel {
+    /// Parses one `SWARM_FEDERATION_CHILDREN` entry
+    fn parse(entry: &str) -> Self {
+        let (configured_site, address) = match entry.split_once('=') {
+            Some((site, address)) if !site.contains(':') => {
+                (Some(site.trim().to_string()), address.trim())
+            }
+            _ => (None, entry),
+        };
+        let address = if address.contains("://") {
+            address.to_string()
+        } else {
+            format!("http://{address}")
+        };
+        Self {
+            site: configured_site.clone().unwrap_or_else(|| address.clone()),
+            address,
+            state: ChildState::Connecting,
+            last_contact: 0,
+            last_error: String::new(),
+            configured_site,
+        }
+    }
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Formats an error with its causes; tonic's transport errors only say
+/// "transport error" at the top level
+pub fn describe(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Fetches the latest report of every node a sentinel knows
+pub async fn fetch_snapshot(address: &str) -> Result<SwarmReportResponse, String> {
+    let channel = Endpoint::from_shared(address.to_string())
+        .map_err(|e| describe(&e))?
+        .connect_timeout(REQUEST_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .connect()
+        .await
+        .map_err(|e| describe(&e))?;
+    let mut client =
+        SwarmReportServiceClient::new(channel).max_decoding_message_size(MAX_SNAPSHOT_BYTES);
+    let response = client
+        .get_swarm_report(tonic::Request::new(SwarmReportRequest {}))
+        .await
+        .map_err(|status| status.message().to_string())?;
+    Ok(response.into_inner())
+}
+
+/// Polls one child forever, handing its nodes to the state actor
+async fn follow_child(state: StateHandle, index: usize, address: String, interval: Duration) {
+    loop {
+        let snapshot = fetch_snapshot(&address).await;
+        state
+            .send(StateEvent::ChildPolled {
+                index,
+                address: address.clone(),
+                snapshot,
+            })
+            .await;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Records the outcome of a poll and merges the child's nodes
+pub fn merge_snapshot(
+    app: &mut App,
+    index: usize,
+    address: &str,
+    snapshot: Result<SwarmReportResponse, String>,
+) {
+    match snapshot {
+        Ok(response) => {
+            let child = &mut app.children[index];
+            if child.state == ChildState::Unreachable {
+                println!("Child sentinel {address} is reachable again");
+            }
+            child.state = ChildState::Connected;
+            child.last_contact = unix_secs();
+            child.last_error.clear();
+            if child.configured_site.is_none() && !response.site.is_empty() {
+                child.site = response.site;
+            }
+
+            let site = child.site.clone();
+            for mut report in response.reports {
+                if report.site.is_empty() {
+                    report.site = site.clone();
+                }
+                app.update_report(ReportEntry::relayed(report, address));
+            }
+        }
+        Err(error) => {
+            let child = &mut app.children[index];
+            if child.state != ChildState::Unreachable {
+                eprintln!("Child sentinel {address} is unreachable: {error}");
+            }
+            child.state = ChildState::Unreachable;
+            child.last_error = error;
+        }
+    }
+}
+
+/// Starts polling every child sentinel in `SWARM_FEDERATION_CHILDREN`; does
+/// nothing when it is unset
+pub async fn run_federation(state: StateHandle) {
+    let children: Vec<ChildSentinel> = std::env::var("SWARM_FEDERATION_CHILDREN")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(ChildSentinel::parse)
+        .collect();
+    if children.is_empty() {
+        return;
+    }
+
+    let interval = std::env::var("SWARM_FEDERATION_INTERVAL")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map_or(Duration::from_secs(5), Duration::from_secs);
+
+    let addresses: Vec<String> = children.iter().map(|c| c.address.clone()).collect();
+    state.send(StateEvent::Children(children)).await;
+    for (index, address) in addresses.into_iter().enumerate() {
+        tokio::spawn(follow_child(state.clone(), index, address, interval));
+    }
+}
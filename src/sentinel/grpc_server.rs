@@ -1,43 +1,48 @@
 /// gRPC server implementation for SwarmReport Sentinel
 ///
-/// Handles incoming system reports from client machines and forwards them
-/// to the state manager via a broadcast channel.
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::broadcast;
+/// Handles incoming system reports from client machines and hands them to
+/// the state actor, and serves the current reports to parent sentinels and
+/// heartbeats to the high availability peer from its latest snapshot.
 use tonic::transport::Server;
 
+use super::ha::{REPLICA_HEADER, Replicator};
+use super::state::{StateEvent, StateHandle};
 use super::types::ReportEntry;
 use crate::swarmreport::swarm_report_service_server::SwarmReportService;
-use crate::swarmreport::{ReportResponse, SwarmReportRequest, SwarmReportResponse, SystemReport};
+use crate::swarmreport::{
+    HaHeartbeat, ReportResponse, SwarmReportRequest, SwarmReportResponse, SystemReport,
+};
 
 pub mod swarmreport {
     tonic::include_proto!("swarmreport");
 }
 
 /// The main Sentinel service that receives reports from clients
-#[derive(Debug)]
 pub struct Sentinel {
-    pub report_sender: broadcast::Sender<ReportEntry>,
+    pub state: StateHandle,
+    /// Set when paired with a high availability peer
+    pub replicator: Option<Replicator>,
 }
 
 #[tonic::async_trait]
 impl SwarmReportService for Sentinel {
-    /// Receives a system report from a client and forwards it to the state manager
+    /// Receives a system report from a client and hands it to the state actor,
+    /// answering once it is queued; while the actor is behind this waits,
+    /// slowing reporters down instead of losing their reports
     async fn send_system_report(
         &self,
         request: tonic::Request<SystemReport>,
     ) -> Result<tonic::Response<ReportResponse>, tonic::Status> {
-        let report = request.into_inner();
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let entry = ReportEntry {
-            report,
-            last_updated: timestamp,
-        };
-        let _ = self.report_sender.send(entry); // Ignore if no receivers
+        if request.metadata().contains_key(REPLICA_HEADER) {
+            let entry = ReportEntry::replicated(request.into_inner());
+            self.state.send(StateEvent::Replicated(entry)).await;
+        } else {
+            let entry = ReportEntry::received(request.into_inner());
+            if let Some(replicator) = &self.replicator {
+                replicator.forward(&entry);
+            }
+            self.state.send(StateEvent::Report(entry)).await;
+        }
 
         Ok(tonic::Response::new(ReportResponse {
             message: "System report received successfully".to_string(),
@@ -45,23 +50,47 @@ impl SwarmReportService for Sentinel {
         }))
     }
 
-    /// Returns aggregated swarm information (currently just a placeholder)
+    /// Returns the latest report of every node, including those relayed by
+    /// child sentinels; parent sentinels poll this in federation mode
     async fn get_swarm_report(
         &self,
         _request: tonic::Request<SwarmReportRequest>,
     ) -> Result<tonic::Response<SwarmReportResponse>, tonic::Status> {
+        let app = self.state.snapshot();
         Ok(tonic::Response::new(SwarmReportResponse {
-            reports: vec![], // TODO: Implement actual swarm report aggregation
+            reports: app.relayable_reports(),
             message: "Swarm report generated successfully".to_string(),
+            site: app.site.clone(),
         }))
     }
+
+    /// Answers the high availability peer's heartbeat with this sentinel's own
+    async fn heartbeat(
+        &self,
+        _request: tonic::Request<HaHeartbeat>,
+    ) -> Result<tonic::Response<HaHeartbeat>, tonic::Status> {
+        match &self.state.snapshot().ha {
+            Some(ha) => Ok(tonic::Response::new(ha.heartbeat())),
+            None => Err(tonic::Status::failed_precondition(
+                "high availability is not configured (SWARM_HA_PEER)",
+            )),
+        }
+    }
+}
+
+/// Port the gRPC server listens on, `SWARM_GRPC_PORT` or 50051
+pub fn grpc_port() -> u16 {
+    std::env::var("SWARM_GRPC_PORT")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(50051)
 }
 
-/// Starts the gRPC server on port 50051
+/// Starts the gRPC server
 pub async fn run_grpc_server(
     sentinel: Sentinel,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let addr = "0.0.0.0:50051".parse()?;
+    let addr = format!("0.0.0.0:{}", grpc_port()).parse()?;
 
     Server::builder()
         .add_service(
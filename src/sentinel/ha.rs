@@ -0,0 +1,439 @@
+/// Active-passive high availability
+///
+/// Two sentinels name each other in `SWARM_HA_PEER`. Every report one of them
+/// receives from a reporter is replicated to the other, and whenever the peer
+/// (re)appears its current reports are pulled, so both show the same nodes.
+/// They exchange heartbeats every second and elect a leader, the only one
+/// that sends alert notifications. The standby takes over once the peer has
+/// been silent for `SWARM_HA_TIMEOUT` seconds; a sentinel that comes back
+/// stays standby until the leader fails in turn.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tonic::transport::{Channel, Endpoint};
+
+use super::federation::{describe, fetch_snapshot};
+use super::grpc_server::grpc_port;
+use super::state::{StateEvent, StateHandle};
+use super::types::ReportEntry;
+use crate::swarmreport::HaHeartbeat;
+use crate::swarmreport::SystemReport;
+use crate::swarmreport::swarm_report_service_client::SwarmReportServiceClient;
+
+/// Metadata key marking a report replicated from the peer, so it is not
+/// replicated back
+pub const REPLICA_HEADER: &str = "x-swarm-replica";
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time allowed for connecting to the peer and for each call
+const PEER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reports waiting to be replicated; more are dropped, the peer catches up
+/// with the next report of the same node
+const REPLICATION_QUEUE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Standby,
+    Leader,
+}
+
+/// This sentinel's side of the pair
+#[derive(Clone, Debug, Serialize)]
+pub struct HaStatus {
+    pub instance_id: String,
+    pub priority: i32,
+    pub role: Role,
+    /// When the current role was taken, Unix seconds
+    pub role_since: u64,
+    pub peer: String,
+    pub peer_instance_id: String,
+    pub peer_role: Option<Role>,
+    /// Last answered heartbeat, Unix seconds
+    pub last_heartbeat: u64,
+    pub peer_error: String,
+    /// Reports replicated to the peer
+    pub replicated: u64,
+    /// Reports received from the peer
+    pub received_from_peer: u64,
+    /// Reports that could not be replicated
+    pub replication_dropped: u64,
+}
+
+impl HaStatus {
+    pub fn heartbeat(&self) -> HaHeartbeat {
+        HaHeartbeat {
+            instance_id: self.instance_id.clone(),
+            priority: self.priority,
+            leader: self.role == Role::Leader,
+        }
+    }
+
+    /// Whether this sentinel may send notifications
+    pub fn is_leader(&self) -> bool {
+        self.role == Role::Leader
+    }
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Decides this sentinel's role from the peer's last heartbeat, or from how
+/// long the peer has been silent when it didn't answer
+fn elect(
+    me: &HaHeartbeat,
+    peer: Option<&HaHeartbeat>,
+    silent_for: Duration,
+    timeout: Duration,
+) -> Option<Role> {
+    let Some(peer) = peer else {
+        return (silent_for >= timeout).then_some(Role::Leader);
+    };
+    let outranks_peer = (me.priority, &me.instance_id) > (peer.priority, &peer.instance_id);
+    Some(match (me.leader, peer.leader) {
+        // Both took over while they couldn't reach each other
+        (true, true) if outranks_peer => Role::Leader,
+        (true, true) => Role::Standby,
+        (false, true) => Role::Standby,
+        (true, false) => Role::Leader,
+        (false, false) if outranks_peer => Role::Leader,
+        (false, false) => Role::Standby,
+    })
+}
+
+/// The outcome of one heartbeat exchange, applied by the state actor
+pub struct PeerHeartbeat {
+    pub answer: Result<HaHeartbeat, String>,
+    /// The elected role, None to keep the current one
+    pub role: Option<Role>,
+    pub replicated: u64,
+    pub replication_dropped: u64,
+}
+
+/// Records a heartbeat exchange in this sentinel's status
+pub fn record_heartbeat(ha: &mut HaStatus, heartbeat: PeerHeartbeat) {
+    match heartbeat.answer {
+        Ok(answer) => {
+            if !ha.peer_error.is_empty() {
+                println!("HA peer {} is answering again", ha.peer);
+            }
+            ha.peer_role = Some(if answer.leader {
+                Role::Leader
+            } else {
+                Role::Standby
+            });
+            ha.peer_instance_id = answer.instance_id;
+            ha.last_heartbeat = unix_secs();
+            ha.peer_error.clear();
+        }
+        Err(error) => {
+            if ha.peer_error.is_empty() {
+                eprintln!("HA peer {} is not answering: {error}", ha.peer);
+            }
+            ha.peer_role = None;
+            ha.peer_error = error;
+        }
+    }
+    if let Some(role) = heartbeat.role
+        && role != ha.role
+    {
+        println!("HA role: {:?} -> {:?}", ha.role, role);
+        ha.role = role;
+        ha.role_since = unix_secs();
+    }
+    ha.replicated = heartbeat.replicated;
+    ha.replication_dropped = heartbeat.replication_dropped;
+}
+
+/// Replication totals, reported to the state actor with each heartbeat
+#[derive(Default)]
+struct ReplicationCounters {
+    replicated: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Queues reports received from reporters for replication to the peer
+#[derive(Clone)]
+pub struct Replicator {
+    sender: mpsc::Sender<SystemReport>,
+    counters: Arc<ReplicationCounters>,
+}
+
+impl Replicator {
+    pub fn forward(&self, entry: &ReportEntry) {
+        let mut report = entry.report.clone();
+        report.received_at = entry.received_at;
+        if self.sender.try_send(report).is_err() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Sends queued reports to the peer in order, skipping ahead while it is down
+async fn run_replication(
+    channel: Channel,
+    mut receiver: mpsc::Receiver<SystemReport>,
+    counters: Arc<ReplicationCounters>,
+) {
+    let mut client = SwarmReportServiceClient::new(channel);
+    let mut retry_at = Instant::now();
+
+    while let Some(report) = receiver.recv().await {
+        let delivered = Instant::now() >= retry_at && {
+            let mut request = tonic::Request::new(report);
+            request.metadata_mut().insert(
+                REPLICA_HEADER,
+                tonic::metadata::MetadataValue::from_static("1"),
+            );
+            client.send_system_report(request).await.is_ok()
+        };
+        if !delivered {
+            retry_at = Instant::now() + HEARTBEAT_INTERVAL;
+        }
+
+        let counter = if delivered {
+            &counters.replicated
+        } else {
+            &counters.dropped
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Pulls the peer's current reports, filling in what was missed while the
+/// two couldn't reach each other
+async fn sync_from_peer(peer: String, state: StateHandle) {
+    match fetch_snapshot(&peer).await {
+        Ok(response) => {
+            let count = response.reports.len();
+            for report in response.reports {
+                state
+                    .send(StateEvent::Report(ReportEntry::replicated(report)))
+                    .await;
+            }
+            println!("Synchronized {count} nodes from HA peer {peer}");
+        }
+        Err(error) => eprintln!("Failed to synchronize from HA peer {peer}: {error}"),
+    }
+}
+
+/// Exchanges heartbeats with the peer and applies the election result
+async fn run_heartbeats(
+    channel: Channel,
+    peer: String,
+    timeout: Duration,
+    mut mine: HaHeartbeat,
+    counters: Arc<ReplicationCounters>,
+    state: StateHandle,
+) {
+    let mut client = SwarmReportServiceClient::new(channel);
+    let mut last_answer = Instant::now();
+    let mut reachable = false;
+
+    loop {
+        let answer = client
+            .heartbeat(tonic::Request::new(mine.clone()))
+            .await
+            .map(tonic::Response::into_inner)
+            .map_err(|status| match std::error::Error::source(&status) {
+                Some(cause) => describe(cause),
+                None => status.message().to_string(),
+            });
+
+        if answer.is_ok() {
+            last_answer = Instant::now();
+            if !reachable {
+                tokio::spawn(sync_from_peer(peer.clone(), state.clone()));
+            }
+        }
+        reachable = answer.is_ok();
+        let role = elect(&mine, answer.as_ref().ok(), last_answer.elapsed(), timeout);
+        if let Some(role) = role {
+            mine.leader = role == Role::Leader;
+        }
+
+        state
+            .send(StateEvent::Heartbeat(PeerHeartbeat {
+                answer,
+                role,
+                replicated: counters.replicated.load(Ordering::Relaxed),
+                replication_dropped: counters.dropped.load(Ordering::Relaxed),
+            }))
+            .await;
+
+        tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+    }
+}
+
+/// Starts high availability when `SWARM_HA_PEER` is set, returning the
+/// replicator that reports from reporters are handed to
+pub async fn spawn_ha(state: StateHandle) -> Option<Replicator> {
+    let peer = std::env::var("SWARM_HA_PEER").ok()?.trim().to_string();
+    if peer.is_empty() {
+        return None;
+    }
+    let peer = if peer.contains("://") {
+        peer
+    } else {
+        format!("http://{peer}")
+    };
+
+    let instance_id = std::env::var("SWARM_HA_ID")
+        .ok()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| {
+            let host = sysinfo::System::host_name().unwrap_or_else(|| "sentinel".to_string());
+            format!("{host}:{}", grpc_port())
+        });
+    let priority = std::env::var("SWARM_HA_PRIORITY")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0);
+    let timeout = std::env::var("SWARM_HA_TIMEOUT")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map_or(Duration::from_secs(10), Duration::from_secs);
+
+    let channel = match Endpoint::from_shared(peer.clone()) {
+        Ok(endpoint) => endpoint
+            .connect_timeout(PEER_TIMEOUT)
+            .timeout(PEER_TIMEOUT)
+            .connect_lazy(),
+        Err(e) => {
+            eprintln!("High availability disabled, invalid SWARM_HA_PEER '{peer}': {e}");
+            return None;
+        }
+    };
+
+    println!("High availability: {instance_id} (priority {priority}) paired with {peer}");
+    let status = HaStatus {
+        instance_id,
+        priority,
+        // Standby until the peer answers or stays silent for the timeout,
+        // so a restarting sentinel doesn't notify alongside the leader
+        role: Role::Standby,
+        role_since: unix_secs(),
+        peer: peer.clone(),
+        peer_instance_id: String::new(),
+        peer_role: None,
+        last_heartbeat: 0,
+        peer_error: String::new(),
+        replicated: 0,
+        received_from_peer: 0,
+        replication_dropped: 0,
+    };
+    let mine = status.heartbeat();
+    state.send(StateEvent::HaStarted(status)).await;
+
+    let counters = Arc::new(ReplicationCounters::default());
+    let (sender, receiver) = mpsc::channel(REPLICATION_QUEUE);
+    tokio::spawn(run_replication(channel.clone(), receiver, counters.clone()));
+    tokio::spawn(run_heartbeats(
+        channel,
+        peer,
+        timeout,
+        mine,
+        counters.clone(),
+        state,
+    ));
+
+    Some(Replicator { sender, counters })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn heartbeat(instance_id: &str, priority: i32, leader: bool) -> HaHeartbeat {
+        HaHeartbeat {
+            instance_id: instance_id.to_string(),
+            priority,
+            leader,
+        }
+    }
+
+    #[test]
+    fn peer_up_higher_priority_leads() {
+        let a = heartbeat("a", 1, false);
+        let b = heartbeat("b", 0, false);
+        assert_eq!(
+            elect(&a, Some(&b), Duration::ZERO, TIMEOUT),
+            Some(Role::Leader)
+        );
+        assert_eq!(
+            elect(&b, Some(&a), Duration::ZERO, TIMEOUT),
+            Some(Role::Standby)
+        );
+    }
+
+    #[test]
+    fn peer_up_existing_leader_keeps_its_role() {
+        // A returning higher-priority sentinel doesn't take over
+        let returning = heartbeat("a", 1, false);
+        let leader = heartbeat("b", 0, true);
+        assert_eq!(
+            elect(&returning, Some(&leader), Duration::ZERO, TIMEOUT),
+            Some(Role::Standby)
+        );
+        assert_eq!(
+            elect(&leader, Some(&returning), Duration::ZERO, TIMEOUT),
+            Some(Role::Leader)
+        );
+    }
+
+    #[test]
+    fn peer_up_both_leading_after_a_split_one_steps_down() {
+        let a = heartbeat("a", 1, true);
+        let b = heartbeat("b", 0, true);
+        assert_eq!(
+            elect(&a, Some(&b), Duration::ZERO, TIMEOUT),
+            Some(Role::Leader)
+        );
+        assert_eq!(
+            elect(&b, Some(&a), Duration::ZERO, TIMEOUT),
+            Some(Role::Standby)
+        );
+    }
+
+    #[test]
+    fn peer_down_standby_takes_over_after_the_timeout() {
+        let me = heartbeat("b", 0, false);
+        assert_eq!(elect(&me, None, Duration::from_secs(3), TIMEOUT), None);
+        assert_eq!(elect(&me, None, TIMEOUT, TIMEOUT), Some(Role::Leader));
+    }
+
+    #[test]
+    fn equal_priority_tie_breaks_on_instance_id() {
+        let a = heartbeat("sentinel-a", 0, false);
+        let b = heartbeat("sentinel-b", 0, false);
+        assert_eq!(
+            elect(&b, Some(&a), Duration::ZERO, TIMEOUT),
+            Some(Role::Leader)
+        );
+        assert_eq!(
+            elect(&a, Some(&b), Duration::ZERO, TIMEOUT),
+            Some(Role::Standby)
+        );
+
+        let a = heartbeat("sentinel-a", 0, true);
+        let b = heartbeat("sentinel-b", 0, true);
+        assert_eq!(
+            elect(&b, Some(&a), Duration::ZERO, TIMEOUT),
+            Some(Role::Leader)
+        );
+        assert_eq!(
+            elect(&a, Some(&b), Duration::ZERO, TIMEOUT),
+            Some(Role::Standby)
+        );
+    }
+}
@@ -1,4 +1,9 @@
+pub mod alerts;
+pub mod bench;
+pub mod federation;
 pub mod grpc_server;
+pub mod ha;
+pub mod state;
 pub mod tui;
 pub mod types;
 pub mod web;
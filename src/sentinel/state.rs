@@ -0,0 +1,220 @@
+/// The sentinel's state actor
+///
+/// One task owns the `App`. Everything that changes it is sent to that task
+/// as a `StateEvent` over a bounded queue: reports from reporters, from the
+/// high availability peer and from child sentinels, and the outcome of polls
+/// and heartbeats. After applying what has queued up the actor publishes an
+/// immutable snapshot, at most every `PUBLISH_INTERVAL`. Readers such as the
+/// TUI, the web API and the alert notifier take the latest snapshot without
+/// locking, so a slow reader never holds up reports. When the queue is full,
+/// senders wait for room instead of dropping reports, which slows down the
+/// gRPC calls of reporters rather than losing what they sent.
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, watch};
+
+use super::federation::{ChildSentinel, merge_snapshot};
+use super::ha::{HaStatus, PeerHeartbeat, record_heartbeat};
+use super::types::{App, ReportEntry};
+use crate::swarmreport::SwarmReportResponse;
+
+/// Events waiting for the actor; senders wait once it is full
+const QUEUE_CAPACITY: usize = 4096;
+
+/// Events applied before the actor considers publishing a snapshot
+const MAX_BATCH: usize = 512;
+
+/// Shortest time between two snapshots, bounding the cost of copying the
+/// state when reports trickle in one by one
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often nodes that stopped reporting are removed
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Seconds without a report after which a node is removed
+const OFFLINE_TIMEOUT: u64 = 60;
+
+/// A change to the sentinel's state
+pub enum StateEvent {
+    /// A report from a reporter, or relayed or synchronized second-hand
+    Report(ReportEntry),
+    /// A report the high availability peer replicated to this sentinel
+    Replicated(ReportEntry),
+    /// The child sentinels configured for federation
+    Children(Vec<ChildSentinel>),
+    /// The outcome of polling a child sentinel
+    ChildPolled {
+        index: usize,
+        address: String,
+        snapshot: Result<SwarmReportResponse, String>,
+    },
+    /// High availability was configured
+    HaStarted(HaStatus),
+    /// The outcome of a heartbeat exchange with the high availability peer
+    Heartbeat(PeerHeartbeat),
+}
+
+/// An event and when it was sent, to measure how long it waited
+struct Queued {
+    event: StateEvent,
+    sent: Instant,
+}
+
+/// How far the state actor is behind, published with every snapshot
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StateMetrics {
+    /// Events applied since the sentinel started
+    pub events_applied: u64,
+    /// Events waiting when the last batch was taken from the queue
+    pub queued: usize,
+    pub max_queued: usize,
+    pub queue_capacity: usize,
+    /// Times a sender had to wait because the queue was full
+    pub senders_blocked: u64,
+    /// Time the last applied event waited in the queue, milliseconds
+    pub last_lag_ms: f64,
+    pub max_lag_ms: f64,
+    pub avg_lag_ms: f64,
+    pub snapshots_published: u64,
+    /// Time taken to copy the state into this snapshot, microseconds
+    pub snapshot_build_us: u64,
+    /// When this snapshot was published, Unix milliseconds
+    pub published_at: i64,
+}
+
+/// Sends events to the state actor and reads its latest snapshot
+#[derive(Clone)]
+pub struct StateHandle {
+    events: mpsc::Sender<Queued>,
+    snapshots: watch::Receiver<Arc<App>>,
+    blocked: Arc<AtomicU64>,
+}
+
+impl StateHandle {
+    /// The latest published state; never waits for the actor
+    pub fn snapshot(&self) -> Arc<App> {
+        self.snapshots.borrow().clone()
+    }
+
+    /// Queues an event, waiting for room when the actor is behind
+    pub async fn send(&self, event: StateEvent) {
+        let queued = Queued {
+            event,
+            sent: Instant::now(),
+        };
+        match self.events.try_send(queued) {
+            Ok(()) | Err(TrySendError::Closed(_)) => {}
+            Err(TrySendError::Full(queued)) => {
+                self.blocked.fetch_add(1, Ordering::Relaxed);
+                let _ = self.events.send(queued).await;
+            }
+        }
+    }
+}
+
+/// Starts the state actor with `app` as its initial state
+pub fn spawn_state_actor(app: App) -> StateHandle {
+    let (events, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    let (publisher, snapshots) = watch::channel(Arc::new(app.clone()));
+    let blocked = Arc::new(AtomicU64::new(0));
+    tokio::spawn(run_state_actor(app, receiver, publisher, blocked.clone()));
+    StateHandle {
+        events,
+        snapshots,
+        blocked,
+    }
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+fn apply(app: &mut App, event: StateEvent) {
+    match event {
+        StateEvent::Report(entry) => app.update_report(entry),
+        StateEvent::Replicated(entry) => {
+            if let Some(ha) = &mut app.ha {
+                ha.received_from_peer += 1;
+            }
+            app.update_report(entry);
+        }
+        StateEvent::Children(children) => app.children = children,
+        StateEvent::ChildPolled {
+            index,
+            address,
+            snapshot,
+        } => merge_snapshot(app, index, &address, snapshot),
+        StateEvent::HaStarted(status) => app.ha = Some(status),
+        StateEvent::Heartbeat(heartbeat) => {
+            if let Some(ha) = &mut app.ha {
+                record_heartbeat(ha, heartbeat);
+            }
+        }
+    }
+}
+
+/// Applies events as they arrive and publishes snapshots of the result
+async fn run_state_actor(
+    mut app: App,
+    mut receiver: mpsc::Receiver<Queued>,
+    publisher: watch::Sender<Arc<App>>,
+    blocked: Arc<AtomicU64>,
+) {
+    let mut metrics = StateMetrics {
+        queue_capacity: QUEUE_CAPACITY,
+        ..StateMetrics::default()
+    };
+    let mut total_lag = Duration::ZERO;
+    let mut batch = Vec::with_capacity(MAX_BATCH);
+    let mut cleanup = tokio::time::interval(CLEANUP_INTERVAL);
+    let mut changed = false;
+    let mut next_publish = tokio::time::Instant::now();
+
+    loop {
+        tokio::select! {
+            count = receiver.recv_many(&mut batch, MAX_BATCH) => {
+                if count == 0 {
+                    return;
+                }
+                metrics.queued = count + receiver.len();
+                metrics.max_queued = metrics.max_queued.max(metrics.queued);
+                for queued in batch.drain(..) {
+                    let lag = queued.sent.elapsed();
+                    apply(&mut app, queued.event);
+                    total_lag += lag;
+                    metrics.events_applied += 1;
+                    metrics.last_lag_ms = lag.as_secs_f64() * 1000.0;
+                    metrics.max_lag_ms = metrics.max_lag_ms.max(metrics.last_lag_ms);
+                }
+                metrics.avg_lag_ms =
+                    total_lag.as_secs_f64() * 1000.0 / metrics.events_applied as f64;
+                changed = true;
+            }
+            _ = cleanup.tick() => {
+                app.remove_offline_clients(OFFLINE_TIMEOUT);
+                changed = true;
+            }
+            _ = tokio::time::sleep_until(next_publish), if changed => {}
+        }
+
+        if changed && tokio::time::Instant::now() >= next_publish {
+            let started = Instant::now();
+            let mut snapshot = app.clone();
+            metrics.snapshots_published += 1;
+            metrics.snapshot_build_us = started.elapsed().as_micros() as u64;
+            metrics.senders_blocked = blocked.load(Ordering::Relaxed);
+            metrics.published_at = unix_millis();
+            snapshot.metrics = metrics.clone();
+            publisher.send_replace(Arc::new(snapshot));
+
+            changed = false;
+            next_publish = tokio::time::Instant::now() + PUBLISH_INTERVAL;
+        }
+    }
+}
@@ -17,7 +17,16 @@ use ratatui::{
 /// Clients are color-coded based on how recently they've reported in.
 use std::time::Duration;
 
-use super::types::{App, ReportEntry, SharedState, parse_cpu_usage};
+use super::alerts::TemperatureRule;
+use super::state::StateHandle;
+use super::types::{
+    App, DiskThresholds, ReportEntry, View, ViewState, format_bytes, format_duration, format_rate,
+    parse_cpu_usage, percent, service_health_label, service_is_up, service_kind_label,
+};
+use crate::swarmreport::{
+    CheckStatus, CpuInfo, DiskInfo, NetworkInterface, ProcessInfo, Service, ServiceHealth,
+    SystemReport, Temperature,
+};
 
 /// Determines border color based on how recently a client reported
 fn get_status_color(seconds_since_update: u64) -> Color {
@@ -28,9 +37,9 @@ fn get_status_color(seconds_since_update: u64) -> Color {
     }
 }
 
-/// Renders the main UI in a lazygit/lazydocker style with multiple information panels
-pub fn ui(f: &mut ratatui::Frame, app: &App) {
-    let ordered_reports = app.get_ordered_reports();
+/// Renders the main UI: the current view above a status bar
+pub fn ui(f: &mut ratatui::Frame, app: &App, view: &ViewState) {
+    let ordered_reports = view.ordered_reports(app);
     let current_time = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
@@ -42,11 +51,39 @@ pub fn ui(f: &mut ratatui::Frame, app: &App) {
         .constraints([Constraint::Min(0), Constraint::Length(1)])
         .split(f.area());
 
+    match view.current_view {
+        View::Nodes => {
+            render_nodes_view(f, main_chunks[0], app, view, &ordered_reports, current_time)
+        }
+        View::Outdated => render_outdated_view(f, main_chunks[0], app),
+        View::Swarm => render_swarm_view(f, main_chunks[0], app),
+        View::Inventory => render_inventory_view(f, main_chunks[0], app),
+        View::Patches => render_patches_view(f, main_chunks[0], app, current_time),
+        View::Tailnet => render_tailnet_view(f, main_chunks[0], app, current_time),
+        View::Sites => render_sites_view(f, main_chunks[0], app, current_time),
+        View::Processes => {
+            render_processes_view(f, main_chunks[0], app, view, &ordered_reports, current_time)
+        }
+    }
+
+    // Render status bar (bottom)
+    render_status_bar(f, main_chunks[1], app, view, &ordered_reports, current_time);
+}
+
+/// Renders the nodes view in a lazygit/lazydocker style with multiple information panels
+fn render_nodes_view(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    app: &App,
+    view: &ViewState,
+    ordered_reports: &[&ReportEntry],
+    current_time: u64,
+) {
     // Content area: split into left and right sections
     let content_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-        .split(main_chunks[0]);
+        .split(area);
 
     // Left side: split into top (clients list) and bottom (overview stats)
     let left_chunks = Layout::default()
@@ -61,32 +98,677 @@ pub fn ui(f: &mut ratatui::Frame, app: &App) {
         .split(content_chunks[1]);
 
     // Render clients list (left top)
+    render_clients_list(f, left_chunks[0], ordered_reports, current_time, app, view);
+
+    // Render overview stats (left bottom)
+    render_overview_stats(f, left_chunks[1], ordered_reports, current_time);
+
+    // Render selected client details (right top)
+    let selected_client = view.get_selected_client(app);
+    render_client_details(f, right_chunks[0], selected_client, app, view, current_time);
+
+    // Render services overview (right bottom)
+    render_services_overview(f, right_chunks[1], selected_client, current_time);
+}
+
+/// Renders the client list next to the selected node's top processes
+fn render_processes_view(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    app: &App,
+    view: &ViewState,
+    ordered_reports: &[&ReportEntry],
+    current_time: u64,
+) {
+    let content_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(area);
+
     render_clients_list(
         f,
-        left_chunks[0],
-        &ordered_reports,
+        content_chunks[0],
+        ordered_reports,
         current_time,
-        app.selected_client_index,
+        app,
+        view,
     );
 
-    // Render overview stats (left bottom)
-    render_overview_stats(f, left_chunks[1], &ordered_reports, current_time);
+    let process_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(content_chunks[1]);
 
-    // Render selected client details (right top)
-    let selected_client = app.get_selected_client();
-    render_client_details(f, right_chunks[0], selected_client, current_time);
+    let (top_cpu, top_memory) = view
+        .get_selected_client(app)
+        .map_or((&[][..], &[][..]), |entry| {
+            (
+                &entry.report.top_cpu_processes[..],
+                &entry.report.top_memory_processes[..],
+            )
+        });
 
-    // Render services overview (right bottom)
-    render_services_overview(f, right_chunks[1], selected_client);
+    render_process_table(f, process_chunks[0], "Top CPU", top_cpu);
+    render_process_table(f, process_chunks[1], "Top Memory", top_memory);
+}
+
+/// Renders one list of processes as aligned rows
+fn render_process_table(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    title: &str,
+    processes: &[ProcessInfo],
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("{title} ({})", processes.len()));
+
+    if processes.is_empty() {
+        let none =
+            Paragraph::new("No processes reported (SWARM_TOP_PROCESSES=0 or older reporter)")
+                .block(block)
+                .style(Style::default().fg(Color::Gray));
+        f.render_widget(none, area);
+        return;
+    }
+
+    let mut items = vec![ListItem::new(Line::from(Span::styled(
+        format!(
+            "{:>7} {:<12} {:>7} {:>10}  {:<16} COMMAND",
+            "PID", "USER", "CPU%", "RSS", "NAME"
+        ),
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )))];
+
+    items.extend(processes.iter().map(|process| {
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{:>7} ", process.pid),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(
+                format!("{:<12.12} ", process.user),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(
+                format!("{:>7.1}", process.cpu_percent),
+                Style::default().fg(cpu_color(process.cpu_percent)),
+            ),
+            Span::styled(
+                format!(" {:>10}  ", format_bytes(process.memory_bytes)),
+                Style::default().fg(Color::Magenta),
+            ),
+            Span::styled(
+                format!("{:<16.16} ", process.name),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                process.command.clone(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]))
+    }));
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
+/// Renders pending OS package updates per node, nodes needing attention first
+fn render_patches_view(f: &mut ratatui::Frame, area: Rect, app: &App, current_time: u64) {
+    let patches = app.get_patch_status();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Patch Status ({})", patches.len()))
+        .title_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    if patches.is_empty() {
+        let none = Paragraph::new("No node has reported package updates yet")
+            .block(block)
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(none, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = patches
+        .iter()
+        .map(|node| {
+            let color = if node.security > 0 {
+                Color::Red
+            } else if node.pending > 0 || node.reboot_required {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            let checked = current_time.saturating_sub(node.checked_at.max(0) as u64);
+
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:<20.20} ", node.hostname),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{:<7}", node.manager),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    format!("{:>4} pending {:>3} security", node.pending, node.security),
+                    Style::default().fg(color),
+                ),
+            ];
+            if node.reboot_required {
+                spans.push(Span::styled(
+                    "  reboot required",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            spans.push(Span::styled(
+                format!("  checked {} ago", format_duration(checked)),
+                Style::default().fg(Color::DarkGray),
+            ));
+
+            let mut lines = vec![Line::from(spans)];
+            if !node.packages.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    format!("    {}", node.packages.join(" ")),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            ListItem::new(lines)
+        })
+        .collect();
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
+/// Renders the machines of every reported tailnet, those without a reporter first
+fn render_tailnet_view(f: &mut ratatui::Frame, area: Rect, app: &App, current_time: u64) {
+    let peers = app.get_tailnet_peers();
+    let unmonitored = peers.iter().filter(|peer| peer.reporter.is_none()).count();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            "Tailnet ({} machines, {} without reporter)",
+            peers.len(),
+            unmonitored
+        ))
+        .title_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    if peers.is_empty() {
+        let none = Paragraph::new("No node has reported Tailscale status yet")
+            .block(block)
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(none, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = peers
+        .iter()
+        .map(|peer| {
+            let (icon, icon_color) = if peer.online {
+                ("●", Color::Green)
+            } else {
+                ("○", Color::DarkGray)
+            };
+            let seen = if peer.online {
+                "online".to_string()
+            } else if peer.last_seen > 0 {
+                let ago = current_time.saturating_sub(peer.last_seen as u64);
+                format!("seen {} ago", format_duration(ago))
+            } else {
+                "offline".to_string()
+            };
+
+            let mut spans = vec![
+                Span::styled(format!("{icon} "), Style::default().fg(icon_color)),
+                Span::styled(
+                    format!("{:<20.20} ", peer.hostname),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{:<16} ", peer.addresses.first().map_or("", |a| a.as_str())),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(format!("{:<8} ", peer.os), Style::default().fg(Color::Gray)),
+                Span::styled(format!("{seen:<16}"), Style::default().fg(Color::Gray)),
+            ];
+            match &peer.reporter {
+                Some(_) => spans.push(Span::styled("reporter", Style::default().fg(Color::Green))),
+                None => spans.push(Span::styled(
+                    "no reporter",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )),
+            }
+            if peer.offers_exit_node {
+                spans.push(Span::styled(
+                    "  exit node",
+                    Style::default().fg(Color::Magenta),
+                ));
+            }
+            spans.push(Span::styled(
+                format!("  {}", peer.tailnet),
+                Style::default().fg(Color::DarkGray),
+            ));
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
+/// Lists this sentinel's site and the sites of its child sentinels
+fn render_sites_view(f: &mut ratatui::Frame, area: Rect, app: &App, current_time: u64) {
+    let sites = app.get_sites();
+    let unreachable = sites
+        .iter()
+        .filter(|site| site.state == "unreachable")
+        .count();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            "Sites ({} sites, {} unreachable)",
+            sites.len(),
+            unreachable
+        ))
+        .title_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let items: Vec<ListItem> = sites
+        .iter()
+        .map(|site| {
+            let (icon, icon_color) = match site.state.as_str() {
+                "local" | "connected" => ("●", Color::Green),
+                "connecting" => ("◐", Color::Yellow),
+                _ => ("○", Color::Red),
+            };
+            let name = if site.site.is_empty() {
+                "(no site)"
+            } else {
+                &site.site
+            };
+            let contact = match site.state.as_str() {
+                "local" => String::new(),
+                _ if site.last_contact == 0 => "never reached".to_string(),
+                _ => format!(
+                    "polled {} ago",
+                    format_duration(current_time.saturating_sub(site.last_contact))
+                ),
+            };
+
+            let mut spans = vec![
+                Span::styled(format!("{icon} "), Style::default().fg(icon_color)),
+                Span::styled(
+                    format!("{name:<20.20} "),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{:<28.28} ", site.sentinel),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!("{:<12}", site.state),
+                    Style::default().fg(icon_color),
+                ),
+                Span::styled(
+                    format!("{:>4} nodes", site.nodes),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    match site.stale_nodes {
+                        0 => String::new(),
+                        stale => format!(" ({stale} stale)"),
+                    },
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(format!("  {contact}"), Style::default().fg(Color::Gray)),
+            ];
+            if !site.error.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", site.error),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
+/// Summarizes clock skew and sequence anomalies, e.g. "+1.2s skew, seq 42, 1 missed"
+fn clock_summary(entry: &ReportEntry) -> String {
+    let mut parts = vec![match entry.stream.clock_skew_ms() {
+        Some(skew) => format!("{:+.1}s skew", skew as f64 / 1000.0),
+        None => "skew unknown".to_string(),
+    }];
+    if entry.report.sequence > 0 {
+        parts.push(format!("seq {}", entry.report.sequence));
+    }
+    for (count, label) in [
+        (entry.stream.missed, "missed"),
+        (entry.stream.duplicates, "duplicate"),
+        (entry.stream.out_of_order, "out of order"),
+    ] {
+        if count > 0 {
+            parts.push(format!("{count} {label}"));
+        }
+    }
+    parts.join(", ")
+}
+
+/// Renders the fleet inventory, one row per node
+fn render_inventory_view(f: &mut ratatui::Frame, area: Rect, app: &App) {
+    let inventory = app.get_inventory();
+
+    let mut items = vec![ListItem::new(Line::from(Span::styled(
+        format!(
+            "{:<20} {:<24} {:<20} {:<8} {:>5} {:>10}  {:<10} {:<8}",
+            "HOST", "OS", "KERNEL", "ARCH", "CORES", "MEMORY", "VIRT", "REPORTER"
+        ),
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    )))];
+
+    items.extend(inventory.iter().map(|node| {
+        if node.received_at == 0 {
+            return ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<20.20} ", node.hostname),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled("inventory pending", Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+
+        let virtualization = match (node.virtualization.as_str(), node.container.as_str()) {
+            ("", "") => "metal".to_string(),
+            (vm, "") => vm.to_string(),
+            (_, container) => container.to_string(),
+        };
+
+        ListItem::new(Line::from(vec![
+            Span::styled(
+                format!("{:<20.20} ", node.hostname),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(
+                format!(
+                    "{:<24.24} ",
+                    format!("{} {}", node.os_name, node.os_version)
+                ),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                format!("{:<20.20} ", node.kernel_version),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(
+                format!("{:<8} ", node.architecture),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(
+                format!("{:>5} ", node.logical_cores),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                format!("{:>10}  ", format_bytes(node.total_memory_bytes)),
+                Style::default().fg(Color::Magenta),
+            ),
+            Span::styled(
+                format!("{virtualization:<10.10} "),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(
+                format!("{:<8}", node.reporter_version),
+                Style::default().fg(Color::Gray),
+            ),
+        ]))
+    }));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Inventory ({})", inventory.len()))
+        .title_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_widget(List::new(items).block(block), area);
+}
+
+/// Renders the fleet-wide list of containers with image updates available
+fn render_outdated_view(f: &mut ratatui::Frame, area: Rect, app: &App) {
+    let outdated = app.get_outdated_services();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Outdated Containers ({})", outdated.len()))
+        .title_style(
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    if outdated.is_empty() {
+        let up_to_date = Paragraph::new("All containers are running their latest image")
+            .block(block)
+            .style(Style::default().fg(Color::Green));
+        f.render_widget(up_to_date, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = outdated
+        .iter()
+        .map(|service| {
+            ListItem::new(Line::from(vec![
+                Span::styled("↑ ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{:<20}", service.hostname),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" {:<30}", service.name),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!(" {}", service.image),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+
+    f.render_widget(List::new(items).block(block), area);
+}
+
+/// Renders the Docker Swarm cluster view with services and nodes side by side
+fn render_swarm_view(f: &mut ratatui::Frame, area: Rect, app: &App) {
+    let clusters = app.get_swarm_clusters();
+
+    if clusters.is_empty() {
+        let no_swarm = Paragraph::new("No Docker Swarm manager is reporting")
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Swarm Cluster"),
+            )
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(no_swarm, area);
+        return;
+    }
+
+    let cluster_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Ratio(1, clusters.len() as u32);
+            clusters.len()
+        ])
+        .split(area);
+
+    for (cluster, cluster_area) in clusters.iter().zip(cluster_chunks.iter()) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(*cluster_area);
+
+        // Under-replicated services first so they can't scroll out of view
+        let mut services: Vec<_> = cluster.services.iter().collect();
+        services.sort_by_key(|s| (!s.under_replicated, s.name.clone()));
+        let under_replicated = services.iter().filter(|s| s.under_replicated).count();
+
+        let service_items: Vec<ListItem> = services
+            .iter()
+            .map(|service| {
+                let color = if service.under_replicated {
+                    Color::Red
+                } else {
+                    Color::Green
+                };
+                let placement = service
+                    .tasks
+                    .iter()
+                    .map(|task| format!("{}: {}", task.node, task.state))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                ListItem::new(vec![
+                    Line::from(vec![
+                        Span::styled(
+                            if service.under_replicated {
+                                "! "
+                            } else {
+                                "✓ "
+                            },
+                            Style::default().fg(color),
+                        ),
+                        Span::styled(
+                            format!("{:<25}", service.name),
+                            Style::default()
+                                .fg(Color::White)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(
+                            format!(
+                                " {:>3}/{:<3}",
+                                service.running_replicas, service.desired_replicas
+                            ),
+                            Style::default().fg(color),
+                        ),
+                        Span::styled(
+                            format!(" {:<10}", service.mode),
+                            Style::default().fg(Color::Gray),
+                        ),
+                        Span::styled(
+                            format!(" {}", service.image),
+                            Style::default().fg(Color::Cyan),
+                        ),
+                    ]),
+                    Line::from(Span::styled(
+                        format!("    {placement}"),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ])
+            })
+            .collect();
+
+        let services_list = List::new(service_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Swarm Services ({} total, {} under-replicated) - via {}",
+                    cluster.services.len(),
+                    under_replicated,
+                    cluster.reported_by
+                ))
+                .title_style(
+                    Style::default()
+                        .fg(if under_replicated > 0 {
+                            Color::Red
+                        } else {
+                            Color::White
+                        })
+                        .add_modifier(Modifier::BOLD),
+                ),
+        );
+        f.render_widget(services_list, chunks[0]);
+
+        let node_items: Vec<ListItem> = cluster
+            .nodes
+            .iter()
+            .map(|node| {
+                let color = match (node.status.as_str(), node.availability.as_str()) {
+                    ("Ready", "Active") => Color::Green,
+                    ("Ready", _) => Color::Yellow,
+                    _ => Color::Red,
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::styled("● ", Style::default().fg(color)),
+                    Span::styled(
+                        format!("{:<18}", node.hostname),
+                        Style::default().fg(Color::White),
+                    ),
+                    Span::styled(
+                        format!(" {:<6} {:<7}", node.status, node.availability),
+                        Style::default().fg(color),
+                    ),
+                    Span::styled(
+                        format!(" {}", node.manager_status),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                ]))
+            })
+            .collect();
 
-    // Render status bar (bottom)
-    render_status_bar(f, main_chunks[1], &ordered_reports, current_time);
+        let nodes_list = List::new(node_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Swarm Nodes ({})", cluster.nodes.len()))
+                .title_style(
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        );
+        f.render_widget(nodes_list, chunks[1]);
+    }
 }
 
 /// Renders the status bar with key bindings and system info
 fn render_status_bar(
     f: &mut ratatui::Frame,
     area: Rect,
+    app: &App,
+    view: &ViewState,
     reports: &[&ReportEntry],
     _current_time: u64,
 ) {
@@ -105,13 +787,34 @@ fn render_status_bar(
         Span::styled(" | ", Style::default().fg(Color::Gray)),
         Span::styled("↑↓/jk: Navigate", Style::default().fg(Color::Yellow)),
         Span::styled(" | ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("Tab: View ({})", view.current_view.title()),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::styled(" | ", Style::default().fg(Color::Gray)),
         Span::styled("r/F5: Refresh", Style::default().fg(Color::Yellow)),
         Span::styled(" | ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("a: Address ({})", view.address_preference.label()),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::styled(" | ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!(
+                "s: Group by site ({})",
+                if view.group_by_site { "on" } else { "off" }
+            ),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::styled(" | ", Style::default().fg(Color::Gray)),
         Span::styled(
             format!("Clients: {}", reports.len()),
             Style::default().fg(Color::Cyan),
         ),
         Span::styled(" | ", Style::default().fg(Color::Gray)),
+        alerts_span(app),
+        ha_span(app),
+        Span::styled(" | ", Style::default().fg(Color::Gray)),
         Span::styled(time_str, Style::default().fg(Color::Gray)),
     ]);
 
@@ -126,8 +829,10 @@ fn render_clients_list(
     area: Rect,
     reports: &[&ReportEntry],
     current_time: u64,
-    selected_index: usize,
+    app: &App,
+    view: &ViewState,
 ) {
+    let selected_index = view.selected_client_index;
     if reports.is_empty() {
         let no_clients = Paragraph::new("No clients connected")
             .block(Block::default().borders(Borders::ALL).title("Clients (0)"))
@@ -136,6 +841,11 @@ fn render_clients_list(
         return;
     }
 
+    // Site tags are only worth the space when nodes come from several sites
+    let show_sites = reports
+        .iter()
+        .any(|entry| entry.report.site != reports[0].report.site);
+
     let items: Vec<ListItem> = reports
         .iter()
         .enumerate()
@@ -154,8 +864,21 @@ fn render_clients_list(
             let datetime = chrono::DateTime::<chrono::Utc>::from(last_updated);
             let time_str = datetime.format("%H:%M:%S").to_string();
 
-            let content = Line::from(vec![
-                Span::styled(format!("{status_icon} "), Style::default().fg(status_color)),
+            let mut spans = vec![Span::styled(
+                format!("{status_icon} "),
+                Style::default().fg(status_color),
+            )];
+            if show_sites {
+                spans.push(Span::styled(
+                    format!("{:<10.10} ", entry.report.site),
+                    Style::default().fg(if app.relay_unreachable(entry) {
+                        Color::Red
+                    } else {
+                        Color::Magenta
+                    }),
+                ));
+            }
+            spans.extend([
                 Span::styled(
                     format!("{:<15}", entry.report.hostname),
                     Style::default()
@@ -163,7 +886,7 @@ fn render_clients_list(
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    format!(" {:>15}", entry.report.ip_address),
+                    format!(" {:>15}", view.address_preference.pick(&entry.report)),
                     Style::default().fg(Color::Cyan),
                 ),
                 Span::styled(
@@ -179,7 +902,7 @@ fn render_clients_list(
                 Span::styled(format!(" {time_str}"), Style::default().fg(Color::Gray)),
             ]);
 
-            let mut item = ListItem::new(content);
+            let mut item = ListItem::new(Line::from(spans));
 
             // Highlight selected item
             if i == selected_index {
@@ -257,7 +980,7 @@ fn render_overview_stats(
                 .report
                 .services
                 .iter()
-                .filter(|s| s.status == "running")
+                .filter(|s| service_is_up(&s.status))
                 .count()
         })
         .sum::<usize>();
@@ -325,6 +1048,8 @@ fn render_client_details(
     f: &mut ratatui::Frame,
     area: Rect,
     selected_client: Option<&ReportEntry>,
+    app: &App,
+    view: &ViewState,
     current_time: u64,
 ) {
     let Some(entry) = selected_client else {
@@ -349,7 +1074,21 @@ fn render_client_details(
     // Create a mini layout for the details
     let detail_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(8), Constraint::Min(0)])
+        .constraints([
+            Constraint::Length(10),
+            Constraint::Length(3),
+            Constraint::Length(cpu_panel_height(entry.report.cpu.as_ref())),
+            Constraint::Length(match entry.report.temperatures.len() {
+                0 => 0,
+                sensors => sensors as u16 + 2,
+            }),
+            Constraint::Length(match entry.report.checks.len() {
+                0 => 0,
+                checks => checks as u16 + 2,
+            }),
+            Constraint::Length(entry.report.disks.len() as u16 + 2),
+            Constraint::Min(0),
+        ])
         .split(area);
 
     // Basic info section
@@ -362,6 +1101,21 @@ fn render_client_details(
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(&entry.report.hostname, Style::default().fg(Color::Cyan)),
+            Span::styled(
+                match (&entry.report.site, &entry.relayed_by) {
+                    (site, _) if site.is_empty() => String::new(),
+                    (site, Some(child)) if app.relay_unreachable(entry) => {
+                        format!("  @ {site} via {child} (unreachable)")
+                    }
+                    (site, Some(child)) => format!("  @ {site} via {child}"),
+                    (site, None) => format!("  @ {site}"),
+                },
+                Style::default().fg(if app.relay_unreachable(entry) {
+                    Color::Red
+                } else {
+                    Color::Magenta
+                }),
+            ),
         ]),
         Line::from(vec![
             Span::styled(
@@ -370,7 +1124,17 @@ fn render_client_details(
                     .fg(Color::White)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(&entry.report.ip_address, Style::default().fg(Color::Cyan)),
+            Span::styled(
+                view.address_preference.pick(&entry.report),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(
+                match entry.report.addresses.len() {
+                    0 | 1 => String::new(),
+                    count => format!(" (+{} more)", count - 1),
+                },
+                Style::default().fg(Color::DarkGray),
+            ),
         ]),
         Line::from(vec![
             Span::styled(
@@ -390,6 +1154,22 @@ fn render_client_details(
             ),
             Span::styled(time_str, Style::default().fg(status_color)),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "Clock: ",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                clock_summary(entry),
+                Style::default().fg(if app.alert_rules.clock.drifting(&entry.stream) {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                }),
+            ),
+        ]),
         Line::from(vec![
             Span::styled(
                 "Memory: ",
@@ -398,7 +1178,19 @@ fn render_client_details(
                     .add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                &entry.report.memory_usage,
+                memory_summary(&entry.report),
+                Style::default().fg(Color::Green),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "Swap: ",
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                swap_summary(&entry.report),
                 Style::default().fg(Color::Green),
             ),
         ]),
@@ -440,6 +1232,445 @@ fn render_client_details(
         .label(format!("{cpu_usage:.1}%"));
 
     f.render_widget(cpu_gauge, detail_chunks[1]);
+
+    if let Some(cpu) = &entry.report.cpu {
+        render_cpu(f, detail_chunks[2], cpu);
+    }
+
+    render_temperatures(
+        f,
+        detail_chunks[3],
+        &entry.report.temperatures,
+        &app.alert_rules.temperature,
+    );
+
+    render_checks(f, detail_chunks[4], &entry.report);
+
+    render_disks(
+        f,
+        detail_chunks[5],
+        &entry.report.disks,
+        &app.disk_thresholds,
+    );
+
+    render_network(f, detail_chunks[6], &entry.report.network_interfaces);
+}
+
+/// Used and total memory with the page cache, falling back to the
+/// preformatted string from older reporters
+fn memory_summary(report: &SystemReport) -> String {
+    let Some(memory) = &report.memory else {
+        return report.memory_usage.clone();
+    };
+    format!(
+        "{} / {} ({:.0}%), {} available, {} cache",
+        format_bytes(memory.used_bytes),
+        format_bytes(memory.total_bytes),
+        percent(memory.used_bytes, memory.total_bytes),
+        format_bytes(memory.available_bytes),
+        format_bytes(memory.cached_bytes + memory.buffers_bytes)
+    )
+}
+
+/// Swap usage and memory pressure, when reported
+fn swap_summary(report: &SystemReport) -> String {
+    let Some(memory) = &report.memory else {
+        return "unknown".to_string();
+    };
+    let mut summary = if memory.swap_total_bytes == 0 {
+        "none".to_string()
+    } else {
+        format!(
+            "{} / {}",
+            format_bytes(memory.swap_used_bytes),
+            format_bytes(memory.swap_total_bytes)
+        )
+    };
+    if let Some(pressure) = &memory.pressure {
+        summary.push_str(&format!(
+            ", pressure {:.1}% (10s) {:.1}% (60s)",
+            pressure.some_avg10, pressure.some_avg60
+        ));
+    }
+    summary
+}
+
+/// High availability role for the status bar; empty when unpaired
+fn ha_span(app: &App) -> Span<'static> {
+    match &app.ha {
+        None => Span::raw(""),
+        Some(ha) if ha.is_leader() => Span::styled(
+            " | HA: leader",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Some(ha) if ha.peer_error.is_empty() => {
+            Span::styled(" | HA: standby", Style::default().fg(Color::Cyan))
+        }
+        Some(_) => Span::styled(
+            " | HA: standby, peer down",
+            Style::default().fg(Color::Yellow),
+        ),
+    }
+}
+
+/// Summarizes firing alerts for the status bar, colored by the worst level
+fn alerts_span(app: &App) -> Span<'static> {
+    let alerts = app.get_alerts();
+    if alerts.is_empty() {
+        return Span::styled("Alerts: 0", Style::default().fg(Color::Green));
+    }
+    let worst = if alerts.iter().any(|alert| alert.level == "crit") {
+        "crit"
+    } else {
+        "warn"
+    };
+    Span::styled(
+        format!("Alerts: {}", alerts.len()),
+        Style::default()
+            .fg(level_color(worst))
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/// Renders plugin check statuses, each followed by the plugin's metrics
+fn render_checks(f: &mut ratatui::Frame, area: Rect, report: &SystemReport) {
+    let items: Vec<ListItem> = report
+        .checks
+        .iter()
+        .map(|check| {
+            let (icon, color) = match check.status() {
+                CheckStatus::Ok => ("✓", Color::Green),
+                CheckStatus::Warning => ("!", Color::Yellow),
+                CheckStatus::Critical => ("✗", Color::Red),
+                CheckStatus::Unknown => ("?", Color::Gray),
+            };
+            let prefix = format!("{}.", check.name);
+            let metrics: Vec<String> = report
+                .custom_metrics
+                .iter()
+                .filter_map(|metric| {
+                    let label = metric.name.strip_prefix(&prefix)?;
+                    Some(format!("{label}={}{}", metric.value, metric.unit))
+                })
+                .collect();
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{icon} "), Style::default().fg(color)),
+                Span::styled(
+                    format!("{:<16.16} ", check.name),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(check.message.clone(), Style::default().fg(color)),
+                Span::styled(
+                    format!("  {}", metrics.join(" ")),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let check_list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Checks ({})", report.checks.len())),
+    );
+    f.render_widget(check_list, area);
+}
+
+/// Renders sensor readings colored by how close they are to critical
+fn render_temperatures(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    temperatures: &[Temperature],
+    rule: &TemperatureRule,
+) {
+    let items: Vec<ListItem> = temperatures
+        .iter()
+        .map(|temperature| {
+            let critical = rule.critical(temperature);
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<28} ", temperature.label),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    usage_bar(temperature.current_celsius / critical * 100.0, 10),
+                    Style::default().fg(level_color(rule.level(temperature))),
+                ),
+                Span::styled(
+                    format!(" {:>5.1}°C", temperature.current_celsius),
+                    Style::default().fg(level_color(rule.level(temperature))),
+                ),
+                Span::styled(
+                    format!(
+                        "  max {:.0}°C  crit {critical:.0}°C",
+                        temperature.max_celsius
+                    ),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let sensor_list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Temperatures ({})", temperatures.len())),
+    );
+    f.render_widget(sensor_list, area);
+}
+
+/// Cores shown side by side in the per-core panel
+fn cpu_columns(cores: usize) -> usize {
+    if cores > 16 { 4 } else { 2 }
+}
+
+/// Height of the per-core panel: borders, summary line and one row per column set
+fn cpu_panel_height(cpu: Option<&CpuInfo>) -> u16 {
+    cpu.map_or(0, |cpu| {
+        let cores = cpu.core_usage.len();
+        (cores.div_ceil(cpu_columns(cores)) + 3) as u16
+    })
+}
+
+fn cpu_color(usage: f64) -> Color {
+    if usage > 80.0 {
+        Color::Red
+    } else if usage > 60.0 {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
+
+/// Renders load, uptime, iowait/steal and a bar per logical core
+fn render_cpu(f: &mut ratatui::Frame, area: Rect, cpu: &CpuInfo) {
+    let cores = cpu.core_usage.len();
+    let columns = cpu_columns(cores);
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled(
+            format!(
+                "load {:.2} {:.2} {:.2}",
+                cpu.load_1, cpu.load_5, cpu.load_15
+            ),
+            Style::default().fg(if cpu.load_1 > cores as f64 {
+                Color::Red
+            } else {
+                Color::White
+            }),
+        ),
+        Span::styled(
+            format!(
+                "  iowait {:.1}%  steal {:.1}%",
+                cpu.iowait_percent, cpu.steal_percent
+            ),
+            Style::default().fg(if cpu.steal_percent > 10.0 || cpu.iowait_percent > 20.0 {
+                Color::Yellow
+            } else {
+                Color::Gray
+            }),
+        ),
+        Span::styled(
+            format!("  up {}", format_duration(cpu.uptime_secs)),
+            Style::default().fg(Color::Gray),
+        ),
+    ])];
+
+    for (row, usages) in cpu.core_usage.chunks(columns).enumerate() {
+        let spans: Vec<Span> = usages
+            .iter()
+            .enumerate()
+            .flat_map(|(column, usage)| {
+                let core = row * columns + column;
+                [
+                    Span::styled(format!("{core:>3} "), Style::default().fg(Color::Gray)),
+                    Span::styled(
+                        usage_bar(*usage, 10),
+                        Style::default().fg(cpu_color(*usage)),
+                    ),
+                    Span::styled(
+                        format!(" {usage:>5.1}%  "),
+                        Style::default().fg(Color::White),
+                    ),
+                ]
+            })
+            .collect();
+        lines.push(Line::from(spans));
+    }
+
+    let title = if cpu.physical_cores > 0 {
+        format!("Cores ({cores} logical, {} physical)", cpu.physical_cores)
+    } else {
+        format!("Cores ({cores})")
+    };
+    let cpu_block =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(cpu_block, area);
+}
+
+/// Renders per-interface throughput, state and error counters
+fn render_network(f: &mut ratatui::Frame, area: Rect, interfaces: &[NetworkInterface]) {
+    let items: Vec<ListItem> = interfaces
+        .iter()
+        .map(|interface| {
+            let (icon, color) = if interface.up {
+                ("▲", Color::Green)
+            } else {
+                ("▼", Color::Red)
+            };
+            let problems = interface.rx_errors
+                + interface.tx_errors
+                + interface.rx_dropped
+                + interface.tx_dropped;
+
+            let mut lines = vec![Line::from(vec![
+                Span::styled(format!("{icon} "), Style::default().fg(color)),
+                Span::styled(
+                    format!("{:<12}", interface.name),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    format!(" ↓{:>11}", format_rate(interface.rx_bytes_per_sec)),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!(" ↑{:>11}", format_rate(interface.tx_bytes_per_sec)),
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::styled(
+                    format!(
+                        " {:.0}/{:.0} pkt/s",
+                        interface.rx_packets_per_sec, interface.tx_packets_per_sec
+                    ),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    if problems > 0 {
+                        format!(
+                            " err {}/{} drop {}/{}",
+                            interface.rx_errors,
+                            interface.tx_errors,
+                            interface.rx_dropped,
+                            interface.tx_dropped
+                        )
+                    } else {
+                        String::new()
+                    },
+                    Style::default().fg(Color::Yellow),
+                ),
+            ])];
+            if !interface.addresses.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    format!("    {}", interface.addresses.join(" ")),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+
+            ListItem::new(lines)
+        })
+        .collect();
+
+    let network_list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Network ({})", interfaces.len())),
+    );
+    f.render_widget(network_list, area);
+}
+
+/// Color for a disk threshold level
+fn level_color(level: &str) -> Color {
+    match level {
+        "crit" => Color::Red,
+        "warn" => Color::Yellow,
+        _ => Color::Green,
+    }
+}
+
+/// Renders a text bar such as "███░░░░░░░" for a percentage
+fn usage_bar(percent: f64, width: usize) -> String {
+    let filled = ((percent / 100.0) * width as f64)
+        .round()
+        .clamp(0.0, width as f64) as usize;
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// Renders per-mount disk usage colored by each mount's thresholds
+fn render_disks(
+    f: &mut ratatui::Frame,
+    area: Rect,
+    disks: &[DiskInfo],
+    disk_thresholds: &DiskThresholds,
+) {
+    let items: Vec<ListItem> = disks
+        .iter()
+        .map(|disk| {
+            let usage = percent(disk.used_bytes, disk.total_bytes);
+            let color = level_color(disk_thresholds.level(&disk.mount_point, usage));
+            let inodes = if disk.total_inodes > 0 {
+                format!(
+                    " inodes {:.0}%",
+                    percent(disk.used_inodes, disk.total_inodes)
+                )
+            } else {
+                String::new()
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<14}", disk.mount_point),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(usage_bar(usage, 10), Style::default().fg(color)),
+                Span::styled(format!(" {usage:>5.1}%"), Style::default().fg(color)),
+                Span::styled(
+                    format!(
+                        " {}/{}",
+                        format_bytes(disk.used_bytes),
+                        format_bytes(disk.total_bytes)
+                    ),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(inodes, Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    if disk.removable { " (removable)" } else { "" },
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let disks_list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Disks ({})", disks.len())),
+    );
+    f.render_widget(disks_list, area);
+}
+
+/// Picks a service's color from its health check first, then its status
+fn service_color(service: &Service) -> Color {
+    if probe_failing(service) {
+        return Color::Magenta;
+    }
+    match (service.status.as_str(), service.health()) {
+        (_, ServiceHealth::Unhealthy) => Color::Red,
+        (_, ServiceHealth::Starting) => Color::Yellow,
+        ("running" | "active", _) => Color::Green,
+        ("stopped" | "exited" | "failed", _) => Color::Red,
+        _ => Color::Yellow,
+    }
+}
+
+/// Whether any active probe against the service is failing
+fn probe_failing(service: &Service) -> bool {
+    service
+        .probes
+        .iter()
+        .any(|probe| probe.status() == CheckStatus::Critical)
 }
 
 /// Renders services for the selected client
@@ -447,6 +1678,7 @@ fn render_services_overview(
     f: &mut ratatui::Frame,
     area: Rect,
     selected_client: Option<&ReportEntry>,
+    current_time: u64,
 ) {
     let Some(entry) = selected_client else {
         let no_services = Paragraph::new("No client selected")
@@ -478,25 +1710,50 @@ fn render_services_overview(
         .services
         .iter()
         .map(|service| {
-            let status_color = match service.status.as_str() {
-                "running" => Color::Green,
-                "stopped" => Color::Red,
-                _ => Color::Yellow,
-            };
+            let status_color = service_color(service);
 
-            let status_icon = match service.status.as_str() {
-                "running" => "✓",
-                "stopped" => "✗",
+            let status_icon = match (service.status.as_str(), service.health()) {
+                _ if probe_failing(service) => "⚠",
+                (_, ServiceHealth::Unhealthy) => "!",
+                ("running" | "active", _) => "✓",
+                ("stopped" | "exited" | "failed", _) => "✗",
                 _ => "?",
             };
 
+            let health = match service_health_label(service.health()) {
+                "" => String::new(),
+                label => format!(" {label}"),
+            };
+
+            let uptime = if service.started_at > 0 && service_is_up(&service.status) {
+                let seconds = current_time.saturating_sub(service.started_at as u64);
+                format!(" up {}", format_duration(seconds))
+            } else {
+                String::new()
+            };
+
+            let restarts = if service.restart_count > 0 {
+                format!(" ↻{}", service.restart_count)
+            } else {
+                String::new()
+            };
+
+            let probes: String = service
+                .probes
+                .iter()
+                .map(|probe| match probe.status() {
+                    CheckStatus::Ok => format!(" {} {:.0}ms", probe.name, probe.latency_ms),
+                    _ => format!(" {}: {}", probe.name, probe.message),
+                })
+                .collect();
+
             let update_indicator = if service.needs_update {
                 " (update available)"
             } else {
                 ""
             };
 
-            let content = Line::from(vec![
+            let summary = Line::from(vec![
                 Span::styled(format!("{status_icon} "), Style::default().fg(status_color)),
                 Span::styled(
                     format!("{:<25}", service.name),
@@ -506,10 +1763,54 @@ fn render_services_overview(
                     format!("{:<10}", service.status),
                     Style::default().fg(status_color),
                 ),
+                Span::styled(
+                    format!("[{}]", service_kind_label(service.kind())),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(health, Style::default().fg(status_color)),
+                Span::styled(uptime, Style::default().fg(Color::Cyan)),
+                Span::styled(restarts, Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    probes,
+                    Style::default().fg(if probe_failing(service) {
+                        Color::Magenta
+                    } else {
+                        Color::DarkGray
+                    }),
+                ),
                 Span::styled(update_indicator, Style::default().fg(Color::Yellow)),
             ]);
 
-            ListItem::new(content)
+            // Second line with image, version, ports and labels where known
+            let mut details = Vec::new();
+            if !service.image.is_empty() {
+                details.push(service.image.clone());
+            }
+            if !service.version.is_empty() && !service.image.ends_with(&service.version) {
+                details.push(format!("v{}", service.version));
+            }
+            if !service.ports.is_empty() {
+                details.push(service.ports.join(", "));
+            }
+            if !service.labels.is_empty() {
+                let mut labels: Vec<String> = service
+                    .labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect();
+                labels.sort();
+                details.push(labels.join(" "));
+            }
+
+            let mut lines = vec![summary];
+            if !details.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    format!("    {}", details.join("  ")),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+
+            ListItem::new(lines)
         })
         .collect();
 
@@ -532,7 +1833,7 @@ fn render_services_overview(
 }
 
 pub async fn run_tui_display_only(
-    state: SharedState,
+    state: StateHandle,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     enable_raw_mode()?;
     let mut stdout = std::io::stdout();
@@ -540,6 +1841,9 @@ pub async fn run_tui_display_only(
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    // Drawing works on a snapshot, so a slow terminal never holds up reports
+    let mut app = state.snapshot();
+    let mut view = ViewState::new();
     let mut last_refresh = std::time::Instant::now();
     let refresh_interval = Duration::from_millis(500); // Refresh every 500ms for more responsive UI
 
@@ -549,53 +1853,36 @@ pub async fn run_tui_display_only(
         let should_refresh = now.duration_since(last_refresh) >= refresh_interval;
 
         if should_refresh {
-            let app = state.lock().unwrap();
-            terminal.draw(|f| ui(f, &app))?;
+            app = state.snapshot();
+            view.validate_selection(app.reports.len());
+            terminal.draw(|f| ui(f, &app, &view))?;
             last_refresh = now;
         }
 
         // Check for input with shorter polling interval
-        if event::poll(Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Esc => break,
-                    KeyCode::Char('r') => {
-                        // Force immediate refresh
-                        let app = state.lock().unwrap();
-                        terminal.draw(|f| ui(f, &app))?;
-                        last_refresh = std::time::Instant::now();
-                    }
-                    KeyCode::F(5) => {
-                        // F5 for refresh (common pattern)
-                        let app = state.lock().unwrap();
-                        terminal.draw(|f| ui(f, &app))?;
-                        last_refresh = std::time::Instant::now();
-                    }
-                    KeyCode::Up | KeyCode::Char('k') => {
-                        // Navigate up in client list
-                        {
-                            let mut app = state.lock().unwrap();
-                            app.select_previous_client();
-                        }
-                        // Force immediate redraw to show selection change
-                        let app = state.lock().unwrap();
-                        terminal.draw(|f| ui(f, &app))?;
-                        last_refresh = std::time::Instant::now();
-                    }
-                    KeyCode::Down | KeyCode::Char('j') => {
-                        // Navigate down in client list
-                        {
-                            let mut app = state.lock().unwrap();
-                            app.select_next_client();
-                        }
-                        // Force immediate redraw to show selection change
-                        let app = state.lock().unwrap();
-                        terminal.draw(|f| ui(f, &app))?;
-                        last_refresh = std::time::Instant::now();
-                    }
-                    _ => {}
-                }
+        if event::poll(Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+        {
+            let count = app.reports.len();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                // Refresh now; F5 is the common alternative
+                KeyCode::Char('r') | KeyCode::F(5) => app = state.snapshot(),
+                // Navigate the client list
+                KeyCode::Up | KeyCode::Char('k') => view.select_previous_client(count),
+                KeyCode::Down | KeyCode::Char('j') => view.select_next_client(count),
+                // Cycle through the address shown for each node
+                KeyCode::Char('a') => view.next_address_preference(),
+                // Toggle grouping the node list by site
+                KeyCode::Char('s') => view.toggle_group_by_site(),
+                // Cycle through views
+                KeyCode::Tab => view.next_view(),
+                _ => continue,
             }
+            // Redraw immediately to show the change
+            view.validate_selection(app.reports.len());
+            terminal.draw(|f| ui(f, &app, &view))?;
+            last_refresh = std::time::Instant::now();
         }
     }
 
@@ -3,21 +3,225 @@ use serde::{Deserialize, Serialize};
 ///
 /// This module defines the core data structures used to store and manage
 /// system reports from multiple client machines.
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+///
+/// Only the latest report of each node is kept. Reports a reporter replays
+/// after an outage are counted by the node's sequence tracking, and the
+/// newest becomes its current state, but the sentinel keeps no history of
+/// them: there is no time series to fill the gap in.
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, OnceLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::swarmreport::SystemReport;
+use super::alerts::{Alert, AlertRules, CertificateRule};
+use super::federation::{ChildSentinel, ChildState};
+use super::ha::HaStatus;
+use super::state::StateMetrics;
+use crate::swarmreport::{
+    AddressFamily, AddressScope, CheckResult, CheckStatus, HostInventory, IpAddress, ProcessInfo,
+    ServiceHealth, ServiceKind, SystemReport, TailscaleStatus,
+};
 
 #[derive(Clone, Debug)]
 pub struct ReportEntry {
     pub report: SystemReport,
+    /// When the sentinel last heard from the node, Unix seconds
     pub last_updated: u64,
+    /// When this report was received, Unix milliseconds
+    pub received_at: i64,
+    /// Sequence and clock tracking, carried over between the node's reports
+    pub stream: ReportStream,
+    /// Address of the child sentinel the report was relayed by, None when
+    /// the node reports to this sentinel directly
+    pub relayed_by: Option<String>,
+    /// Replicated from the high availability peer rather than received from
+    /// the node
+    pub replicated: bool,
+}
+
+impl ReportEntry {
+    /// Identifies the node in `App`'s maps: its node id, or the hostname for
+    /// reporters that don't send one. Keying by node id means a changing or
+    /// additional address doesn't split one node into several entries.
+    pub fn node_key(&self) -> &str {
+        if self.report.node_id.is_empty() {
+            &self.report.hostname
+        } else {
+            &self.report.node_id
+        }
+    }
+
+    /// Wraps a report that has just been received
+    pub fn received(report: SystemReport) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            report,
+            last_updated: now.as_secs(),
+            received_at: now.as_millis() as i64,
+            stream: ReportStream::default(),
+            relayed_by: None,
+            replicated: false,
+        }
+    }
+
+    /// Wraps a report replicated from the high availability peer, keeping
+    /// the time the peer received it
+    pub fn replicated(report: SystemReport) -> Self {
+        let mut entry = Self::received(report);
+        if entry.report.received_at > 0 {
+            entry.received_at = entry.report.received_at;
+            entry.last_updated = (entry.received_at / 1000) as u64;
+        }
+        entry.replicated = true;
+        entry
+    }
+
+    /// Whether the report reached this sentinel second-hand, so repeats and
+    /// gaps in the node's sequence are expected
+    fn second_hand(&self) -> bool {
+        self.relayed_by.is_some() || self.replicated
+    }
+
+    /// Wraps a report relayed by the child sentinel at `child`, keeping the
+    /// time the child received it so the node ages as it does there
+    pub fn relayed(report: SystemReport, child: &str) -> Self {
+        let received_at = if report.received_at > 0 {
+            report.received_at
+        } else {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64
+        };
+        Self {
+            report,
+            last_updated: (received_at / 1000) as u64,
+            received_at,
+            stream: ReportStream::default(),
+            relayed_by: Some(child.to_string()),
+            replicated: false,
+        }
+    }
+}
+
+/// How an incoming report relates to the ones already received from the node
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arrival {
+    /// Newer than anything received so far
+    Next,
+    /// Same reporter run and sequence number as an earlier report
+    Duplicate,
+    /// Older than a report already received
+    OutOfOrder,
+}
+
+/// Tracks the sequence numbers and clock of one node's reports
+#[derive(Clone, Debug, Default)]
+pub struct ReportStream {
+    pub reporter_started_at: i64,
+    pub last_sequence: u64,
+    pub duplicates: u64,
+    pub out_of_order: u64,
+    /// Reports skipped in the sequence, e.g. dropped from a full buffer
+    pub missed: u64,
+    /// Recent differences between the reporter's send time and the
+    /// sentinel's receive time in milliseconds, oldest first
+    skew_samples: VecDeque<i64>,
+}
+
+impl ReportStream {
+    /// Number of samples the clock skew is the median of
+    const SKEW_SAMPLES: usize = 9;
+
+    /// Records an incoming report and classifies it; reports from reporters
+    /// that don't send sequence numbers always count as the next one
+    pub fn observe(&mut self, report: &SystemReport, received_at: i64) -> Arrival {
+        self.record_skew(report, received_at);
+        if report.sequence == 0 {
+            return Arrival::Next;
+        }
+
+        match report.reporter_started_at.cmp(&self.reporter_started_at) {
+            std::cmp::Ordering::Less => {
+                self.out_of_order += 1;
+                Arrival::OutOfOrder
+            }
+            std::cmp::Ordering::Greater => {
+                // A restarted reporter counts from 1 again; before the first
+                // report there is nothing to have missed
+                if self.reporter_started_at != 0 {
+                    self.missed += report.sequence - 1;
+                }
+                self.reporter_started_at = report.reporter_started_at;
+                self.last_sequence = report.sequence;
+                Arrival::Next
+            }
+            std::cmp::Ordering::Equal if report.sequence == self.last_sequence => {
+                self.duplicates += 1;
+                Arrival::Duplicate
+            }
+            std::cmp::Ordering::Equal if report.sequence < self.last_sequence => {
+                self.out_of_order += 1;
+                Arrival::OutOfOrder
+            }
+            std::cmp::Ordering::Equal => {
+                self.missed += report.sequence - self.last_sequence - 1;
+                self.last_sequence = report.sequence;
+                Arrival::Next
+            }
+        }
+    }
+
+    /// Records a report relayed by a child sentinel. The child is polled, so
+    /// the same report arrives repeatedly and most of the sequence is never
+    /// seen; neither is counted, only newer reports are taken.
+    pub fn follow(&mut self, report: &SystemReport, received_at: i64) -> Arrival {
+        if report.sequence != 0 {
+            match (report.reporter_started_at, report.sequence)
+                .cmp(&(self.reporter_started_at, self.last_sequence))
+            {
+                std::cmp::Ordering::Less => return Arrival::OutOfOrder,
+                std::cmp::Ordering::Equal => return Arrival::Duplicate,
+                std::cmp::Ordering::Greater => {
+                    self.reporter_started_at = report.reporter_started_at;
+                    self.last_sequence = report.sequence;
+                }
+            }
+        }
+        self.record_skew(report, received_at);
+        Arrival::Next
+    }
+
+    fn record_skew(&mut self, report: &SystemReport, received_at: i64) {
+        if report.sent_at > 0 {
+            self.skew_samples.push_back(report.sent_at - received_at);
+            if self.skew_samples.len() > Self::SKEW_SAMPLES {
+                self.skew_samples.pop_front();
+            }
+        }
+    }
+
+    /// How far the node's clock is ahead of the sentinel's (negative when
+    /// behind) in milliseconds, including network latency; None until the
+    /// reporter sends its send time
+    pub fn clock_skew_ms(&self) -> Option<i64> {
+        if self.skew_samples.is_empty() {
+            return None;
+        }
+        let mut samples: Vec<i64> = self.skew_samples.iter().copied().collect();
+        samples.sort_unstable();
+        Some(samples[samples.len() / 2])
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct WebClient {
     pub hostname: String,
+    /// Site the node reports at, empty when no site is configured
+    pub site: String,
+    /// Child sentinel the node is seen through, None when it reports here
+    pub relayed_by: Option<String>,
     pub ip_address: String,
     pub node_id: String,
     pub cpu_usage: f64,
@@ -27,6 +231,256 @@ pub struct WebClient {
     pub seconds_since_update: u64,
     pub status: String,
     pub services: Vec<WebService>,
+    pub disks: Vec<WebDisk>,
+    pub network_interfaces: Vec<WebNetworkInterface>,
+    pub cpu: WebCpu,
+    pub memory: Option<WebMemory>,
+    pub temperatures: Vec<WebTemperature>,
+    pub top_cpu_processes: Vec<WebProcess>,
+    pub top_memory_processes: Vec<WebProcess>,
+    pub checks: Vec<WebCheck>,
+    pub custom_metrics: Vec<WebMetric>,
+    pub tailscale: Option<WebTailscale>,
+    pub addresses: Vec<WebAddress>,
+    pub stream: WebReportStream,
+}
+
+/// A site and its nodes, as seen from this sentinel
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebSite {
+    pub site: String,
+    /// "local", or the address of the child sentinel the site is reached through
+    pub sentinel: String,
+    /// "local", "connecting", "connected" or "unreachable"
+    pub state: String,
+    pub nodes: usize,
+    /// Nodes that haven't reported for over 30 seconds
+    pub stale_nodes: usize,
+    /// Last successful poll of the child sentinel, Unix seconds
+    pub last_contact: u64,
+    pub error: String,
+}
+
+/// Sequence and clock figures for one node's reports
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebReportStream {
+    pub sequence: u64,
+    pub duplicates: u64,
+    pub out_of_order: u64,
+    pub missed: u64,
+    pub clock_skew_ms: Option<i64>,
+    /// Whether the skew exceeds `SWARM_CLOCK_SKEW_MAX`
+    pub clock_drift: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebAddress {
+    pub address: String,
+    pub prefix_length: u32,
+    pub interface: String,
+    /// "ipv4" or "ipv6"
+    pub family: String,
+    /// "lan", "tailscale", "public" or "link-local"
+    pub scope: String,
+}
+
+impl From<&IpAddress> for WebAddress {
+    fn from(address: &IpAddress) -> Self {
+        Self {
+            address: address.address.clone(),
+            prefix_length: address.prefix_length,
+            interface: address.interface.clone(),
+            family: match address.family() {
+                AddressFamily::Ipv6 => "ipv6",
+                _ => "ipv4",
+            }
+            .to_string(),
+            scope: address_scope_label(address.scope()).to_string(),
+        }
+    }
+}
+
+/// A node's own tailnet membership
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebTailscale {
+    pub backend_state: String,
+    pub tailnet: String,
+    pub dns_name: String,
+    pub addresses: Vec<String>,
+    pub offers_exit_node: bool,
+    pub exit_node: String,
+    pub key_expiry: i64,
+    pub peers_online: usize,
+    pub peers_total: usize,
+}
+
+impl From<&TailscaleStatus> for WebTailscale {
+    fn from(status: &TailscaleStatus) -> Self {
+        Self {
+            backend_state: status.backend_state.clone(),
+            tailnet: status.tailnet.clone(),
+            dns_name: status.dns_name.clone(),
+            addresses: status.addresses.clone(),
+            offers_exit_node: status.offers_exit_node,
+            exit_node: status.exit_node.clone(),
+            key_expiry: status.key_expiry,
+            peers_online: status.peers.iter().filter(|peer| peer.online).count(),
+            peers_total: status.peers.len(),
+        }
+    }
+}
+
+/// A machine in a tailnet, merged from every reporter that can see it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebTailnetPeer {
+    pub tailnet: String,
+    pub hostname: String,
+    pub dns_name: String,
+    pub addresses: Vec<String>,
+    pub os: String,
+    pub online: bool,
+    pub offers_exit_node: bool,
+    pub last_seen: i64,
+    pub key_expiry: i64,
+    /// Node id of the reporter running on the machine, None when it has none
+    pub reporter: Option<String>,
+    /// Hostnames of the reporters that see the machine as a peer
+    pub seen_by: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebCheck {
+    pub name: String,
+    /// "ok", "warning", "critical" or "unknown"
+    pub status: String,
+    pub message: String,
+    pub checked_at: i64,
+    pub latency_ms: f64,
+}
+
+impl From<&CheckResult> for WebCheck {
+    fn from(check: &CheckResult) -> Self {
+        Self {
+            name: check.name.clone(),
+            status: check_status_label(check.status()).to_string(),
+            message: check.message.clone(),
+            checked_at: check.checked_at,
+            latency_ms: check.latency_ms,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebMetric {
+    pub name: String,
+    pub value: f64,
+    pub unit: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebProcess {
+    pub pid: u32,
+    pub name: String,
+    pub user: String,
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub command: String,
+}
+
+impl From<&ProcessInfo> for WebProcess {
+    fn from(process: &ProcessInfo) -> Self {
+        Self {
+            pid: process.pid,
+            name: process.name.clone(),
+            user: process.user.clone(),
+            cpu_percent: process.cpu_percent,
+            memory_bytes: process.memory_bytes,
+            command: process.command.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebTemperature {
+    pub label: String,
+    pub current_celsius: f64,
+    pub max_celsius: f64,
+    /// Hardware threshold, or the configured default when the sensor has none
+    pub critical_celsius: f64,
+    /// "ok", "warn" or "crit" against the temperature alert rule
+    pub level: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebMemory {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    pub free_bytes: u64,
+    pub cached_bytes: u64,
+    pub buffers_bytes: u64,
+    pub swap_total_bytes: u64,
+    pub swap_used_bytes: u64,
+    pub usage_percent: f64,
+    pub swap_percent: f64,
+    /// Pressure stall averages (some/full over 10s, 60s, 300s), None without PSI
+    pub pressure: Option<WebMemoryPressure>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebMemoryPressure {
+    pub some_avg10: f64,
+    pub some_avg60: f64,
+    pub some_avg300: f64,
+    pub full_avg10: f64,
+    pub full_avg60: f64,
+    pub full_avg300: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebCpu {
+    pub load_1: f64,
+    pub load_5: f64,
+    pub load_15: f64,
+    pub boot_time: u64,
+    pub uptime_secs: u64,
+    pub physical_cores: u32,
+    pub core_usage: Vec<f64>,
+    pub iowait_percent: f64,
+    pub steal_percent: f64,
+    pub brand: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebNetworkInterface {
+    pub name: String,
+    pub up: bool,
+    pub addresses: Vec<String>,
+    pub mac_address: String,
+    pub mtu: u64,
+    pub rx_bytes_per_sec: f64,
+    pub tx_bytes_per_sec: f64,
+    pub rx_packets_per_sec: f64,
+    pub tx_packets_per_sec: f64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_dropped: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebDisk {
+    pub mount_point: String,
+    pub device: String,
+    pub file_system: String,
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    pub available_bytes: u64,
+    pub usage_percent: f64,
+    pub inode_percent: f64,
+    pub removable: bool,
+    /// "ok", "warn" or "crit" against the mount's thresholds
+    pub level: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -34,22 +488,569 @@ pub struct WebService {
     pub name: String,
     pub status: String,
     pub needs_update: bool,
+    pub image: String,
+    pub kind: String,
+    pub sub_state: String,
+    pub restart_count: u32,
+    pub since: i64,
+    pub health: String,
+    pub started_at: i64,
+    pub version: String,
+    pub ports: Vec<String>,
+    pub labels: HashMap<String, String>,
+    pub probes: Vec<WebCheck>,
+}
+
+/// One row of the fleet inventory; host facts are empty until the node's
+/// first inventory arrives (`received_at` is 0)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebInventory {
+    pub hostname: String,
+    pub node_id: String,
+    pub ip_address: String,
+    pub os_name: String,
+    pub os_version: String,
+    pub distribution_id: String,
+    pub kernel_version: String,
+    pub architecture: String,
+    pub cpu_model: String,
+    pub logical_cores: u32,
+    pub physical_cores: u32,
+    pub total_memory_bytes: u64,
+    pub virtualization: String,
+    pub container: String,
+    pub reporter_version: String,
+    pub received_at: u64,
+}
+
+impl WebInventory {
+    /// Column names, in the order `csv_fields` returns values
+    pub const CSV_HEADER: [&'static str; 16] = [
+        "hostname",
+        "node_id",
+        "ip_address",
+        "os_name",
+        "os_version",
+        "distribution_id",
+        "kernel_version",
+        "architecture",
+        "cpu_model",
+        "logical_cores",
+        "physical_cores",
+        "total_memory_bytes",
+        "virtualization",
+        "container",
+        "reporter_version",
+        "received_at",
+    ];
+
+    pub fn csv_fields(&self) -> [String; 16] {
+        [
+            self.hostname.clone(),
+            self.node_id.clone(),
+            self.ip_address.clone(),
+            self.os_name.clone(),
+            self.os_version.clone(),
+            self.distribution_id.clone(),
+            self.kernel_version.clone(),
+            self.architecture.clone(),
+            self.cpu_model.clone(),
+            self.logical_cores.to_string(),
+            self.physical_cores.to_string(),
+            self.total_memory_bytes.to_string(),
+            self.virtualization.clone(),
+            self.container.clone(),
+            self.reporter_version.clone(),
+            self.received_at.to_string(),
+        ]
+    }
+}
+
+/// Pending OS package updates on one node
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebPatchStatus {
+    pub hostname: String,
+    pub node_id: String,
+    pub manager: String,
+    pub pending: u32,
+    pub security: u32,
+    pub reboot_required: bool,
+    pub packages: Vec<String>,
+    pub checked_at: i64,
+}
+
+/// A TLS certificate watched by one node
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebCertificate {
+    pub hostname: String,
+    pub node_id: String,
+    pub source: String,
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_after: i64,
+    pub days_remaining: i64,
+    /// "ok", "warn" or "crit"
+    pub level: String,
+    pub error: String,
+    pub checked_at: i64,
+}
+
+/// A container running an image that is behind its registry tag
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OutdatedService {
+    pub hostname: String,
+    pub node_id: String,
+    pub name: String,
+    pub image: String,
+}
+
+/// A Docker Swarm cluster as reported by one of its managers
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebSwarmCluster {
+    pub cluster_id: String,
+    pub reported_by: String,
+    pub last_updated: u64,
+    pub services: Vec<WebSwarmService>,
+    pub nodes: Vec<WebSwarmNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebSwarmService {
+    pub name: String,
+    pub image: String,
+    pub mode: String,
+    pub desired_replicas: u32,
+    pub running_replicas: u32,
+    pub under_replicated: bool,
+    pub tasks: Vec<WebSwarmTask>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebSwarmTask {
+    pub name: String,
+    pub node: String,
+    pub state: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebSwarmNode {
+    pub hostname: String,
+    pub status: String,
+    pub availability: String,
+    pub manager_status: String,
+    pub engine_version: String,
+}
+
+/// The views the TUI can switch between
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    Nodes,
+    Outdated,
+    Swarm,
+    Processes,
+    Inventory,
+    Patches,
+    Tailnet,
+    Sites,
+}
+
+impl View {
+    /// All views in the order Tab cycles through them
+    const ALL: [View; 8] = [
+        View::Nodes,
+        View::Processes,
+        View::Inventory,
+        View::Patches,
+        View::Tailnet,
+        View::Sites,
+        View::Outdated,
+        View::Swarm,
+    ];
+
+    /// Returns the view that follows this one when cycling with Tab
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|v| *v == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            View::Nodes => "Nodes",
+            View::Outdated => "Outdated Containers",
+            View::Swarm => "Swarm Cluster",
+            View::Processes => "Processes",
+            View::Inventory => "Inventory",
+            View::Patches => "Patch Status",
+            View::Tailnet => "Tailnet",
+            View::Sites => "Sites",
+        }
+    }
 }
 
+/// Warning and critical usage percentages for a mount point
+#[derive(Clone, Copy, Debug)]
+pub struct DiskThreshold {
+    pub warn: f64,
+    pub crit: f64,
+}
+
+/// Disk usage thresholds, configurable per mount point with
+/// `SWARM_DISK_THRESHOLDS`, e.g. "/var=70:85,/=80:90,*=85:95"
+pub struct DiskThresholds {
+    default: DiskThreshold,
+    per_mount: HashMap<String, DiskThreshold>,
+}
+
+impl DiskThresholds {
+    pub fn from_env() -> Self {
+        let mut thresholds = Self {
+            default: DiskThreshold {
+                warn: 80.0,
+                crit: 90.0,
+            },
+            per_mount: HashMap::new(),
+        };
+
+        let config = std::env::var("SWARM_DISK_THRESHOLDS").unwrap_or_default();
+        for rule in config.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let parsed = rule.split_once('=').and_then(|(mount, levels)| {
+                let (warn, crit) = levels.split_once(':')?;
+                Some((
+                    mount.trim(),
+                    DiskThreshold {
+                        warn: warn.trim().parse().ok()?,
+                        crit: crit.trim().parse().ok()?,
+                    },
+                ))
+            });
+
+            match parsed {
+                Some(("*", threshold)) => thresholds.default = threshold,
+                Some((mount, threshold)) => {
+                    thresholds.per_mount.insert(mount.to_string(), threshold);
+                }
+                None => eprintln!("Ignoring malformed disk threshold '{rule}'"),
+            }
+        }
+
+        thresholds
+    }
+
+    pub fn for_mount(&self, mount_point: &str) -> DiskThreshold {
+        self.per_mount
+            .get(mount_point)
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Classifies a usage percentage as "ok", "warn" or "crit"
+    pub fn level(&self, mount_point: &str, usage_percent: f64) -> &'static str {
+        let threshold = self.for_mount(mount_point);
+        if usage_percent >= threshold.crit {
+            "crit"
+        } else if usage_percent >= threshold.warn {
+            "warn"
+        } else {
+            "ok"
+        }
+    }
+}
+
+/// Percentage of `used` in `total`, 0 when the total is unknown
+pub fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 / total as f64 * 100.0
+    }
+}
+
+/// Unit system used to display byte counts, set with `SWARM_BYTE_UNITS`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteUnits {
+    /// Powers of 1024: KiB, MiB, GiB
+    Binary,
+    /// Powers of 1000: kB, MB, GB
+    Decimal,
+}
+
+impl ByteUnits {
+    /// Reads `SWARM_BYTE_UNITS` once; anything but "decimal" means binary
+    pub fn get() -> Self {
+        static UNITS: OnceLock<ByteUnits> = OnceLock::new();
+        *UNITS.get_or_init(|| match std::env::var("SWARM_BYTE_UNITS").as_deref() {
+            Ok("decimal") => ByteUnits::Decimal,
+            _ => ByteUnits::Binary,
+        })
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ByteUnits::Binary => "binary",
+            ByteUnits::Decimal => "decimal",
+        }
+    }
+}
+
+/// Formats a byte count in the configured unit system, e.g. "42.0 GiB"
+pub fn format_bytes(bytes: u64) -> String {
+    let (base, units) = match ByteUnits::get() {
+        ByteUnits::Binary => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+        ByteUnits::Decimal => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB"]),
+    };
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", units[unit])
+    }
+}
+
+/// The sentinel's state, owned by the state actor and published to readers
+/// as immutable snapshots; entries are shared between snapshots, so taking
+/// one copies pointers rather than reports
+#[derive(Clone)]
 pub struct App {
-    pub reports: HashMap<String, ReportEntry>,
+    pub reports: HashMap<String, Arc<ReportEntry>>,
     pub report_order: Vec<String>,
+    pub disk_thresholds: Arc<DiskThresholds>,
+    pub alert_rules: Arc<AlertRules>,
+    /// Latest host inventory per node, keyed like `reports`, kept across the
+    /// reports that omit it
+    pub inventories: HashMap<String, Arc<ReceivedInventory>>,
+    /// This sentinel's site (`SWARM_SITE`), given to nodes that report here
+    pub site: String,
+    /// Child sentinels polled in federation mode
+    pub children: Vec<ChildSentinel>,
+    /// High availability pairing, None when `SWARM_HA_PEER` is unset
+    pub ha: Option<HaStatus>,
+    /// How far the state actor is behind, as of this snapshot
+    pub metrics: StateMetrics,
+}
+
+/// What the TUI shows, kept by the TUI itself so switching views never
+/// waits for the state actor
+pub struct ViewState {
+    pub current_view: View,
     pub selected_client_index: usize,
+    /// Address shown for each node, switched with 'a'
+    pub address_preference: AddressPreference,
+    /// Whether node lists are grouped by site, toggled with 's'
+    pub group_by_site: bool,
+}
+
+impl ViewState {
+    pub fn new() -> Self {
+        Self {
+            current_view: View::Nodes,
+            selected_client_index: 0,
+            address_preference: AddressPreference::from_env(),
+            group_by_site: true,
+        }
+    }
+
+    /// Switch to the next view
+    pub fn next_view(&mut self) {
+        self.current_view = self.current_view.next();
+    }
+
+    /// Switch the address displayed for each node
+    pub fn next_address_preference(&mut self) {
+        self.address_preference = self.address_preference.next();
+    }
+
+    /// Switch between grouping nodes by site and arrival order
+    pub fn toggle_group_by_site(&mut self) {
+        self.group_by_site = !self.group_by_site;
+    }
+
+    /// Reports in the order the node list shows them
+    pub fn ordered_reports<'a>(&self, app: &'a App) -> Vec<&'a ReportEntry> {
+        if self.group_by_site {
+            app.get_ordered_reports()
+        } else {
+            app.reports_in_arrival_order()
+        }
+    }
+
+    /// Navigate to the next of `count` clients
+    pub fn select_next_client(&mut self, count: usize) {
+        if count > 0 {
+            self.selected_client_index = (self.selected_client_index + 1) % count;
+        }
+    }
+
+    /// Navigate to the previous of `count` clients
+    pub fn select_previous_client(&mut self, count: usize) {
+        if count > 0 {
+            self.selected_client_index = if self.selected_client_index == 0 {
+                count - 1
+            } else {
+                self.selected_client_index - 1
+            };
+        }
+    }
+
+    /// Get the currently selected client report
+    pub fn get_selected_client<'a>(&self, app: &'a App) -> Option<&'a ReportEntry> {
+        self.ordered_reports(app)
+            .get(self.selected_client_index)
+            .copied()
+    }
+
+    /// Ensure selected index is valid after clients went offline
+    pub fn validate_selection(&mut self, count: usize) {
+        self.selected_client_index = self.selected_client_index.min(count.saturating_sub(1));
+    }
 }
 
-/// Thread-safe shared state for the application
-pub type SharedState = Arc<Mutex<App>>;
+/// A host inventory and when the sentinel received it
+#[derive(Clone, Debug)]
+pub struct ReceivedInventory {
+    pub inventory: HostInventory,
+    pub received_at: u64,
+}
 
 /// Parses CPU usage string (e.g., "45.2%") into a float
 pub fn parse_cpu_usage(cpu_str: &str) -> f64 {
     cpu_str.trim_end_matches('%').parse::<f64>().unwrap_or(0.0)
 }
 
+/// Short label for where a service was discovered
+pub fn service_kind_label(kind: ServiceKind) -> &'static str {
+    match kind {
+        ServiceKind::Container => "container",
+        ServiceKind::Systemd => "systemd",
+        ServiceKind::Process => "process",
+        ServiceKind::Custom => "custom",
+        ServiceKind::Unspecified => "unknown",
+    }
+}
+
+/// Short label for a service's health check result, empty without a check
+pub fn service_health_label(health: ServiceHealth) -> &'static str {
+    match health {
+        ServiceHealth::Healthy => "healthy",
+        ServiceHealth::Unhealthy => "unhealthy",
+        ServiceHealth::Starting => "starting",
+        ServiceHealth::Unknown => "",
+    }
+}
+
+/// Which of a node's addresses the TUI and dashboard display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressPreference {
+    /// The address the reporter chose (Tailscale, else the default route's)
+    Reported,
+    Tailscale,
+    Lan,
+    Public,
+}
+
+impl AddressPreference {
+    const ALL: [AddressPreference; 4] = [
+        AddressPreference::Reported,
+        AddressPreference::Tailscale,
+        AddressPreference::Lan,
+        AddressPreference::Public,
+    ];
+
+    /// Reads `SWARM_DISPLAY_ADDRESS`, defaulting to the reported address
+    pub fn from_env() -> Self {
+        let configured = std::env::var("SWARM_DISPLAY_ADDRESS").unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|preference| preference.label() == configured.trim())
+            .unwrap_or(AddressPreference::Reported)
+    }
+
+    /// Returns the preference that follows this one when cycling
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AddressPreference::Reported => "reported",
+            AddressPreference::Tailscale => "tailscale",
+            AddressPreference::Lan => "lan",
+            AddressPreference::Public => "public",
+        }
+    }
+
+    /// Picks the first address in the preferred scope, IPv4 before IPv6,
+    /// falling back to the reported address
+    pub fn pick(self, report: &SystemReport) -> &str {
+        let scope = match self {
+            AddressPreference::Reported => return &report.ip_address,
+            AddressPreference::Tailscale => AddressScope::Tailscale,
+            AddressPreference::Lan => AddressScope::Lan,
+            AddressPreference::Public => AddressScope::Public,
+        };
+        let mut candidates: Vec<&IpAddress> = report
+            .addresses
+            .iter()
+            .filter(|address| address.scope() == scope)
+            .collect();
+        candidates.sort_by_key(|address| address.family() != AddressFamily::Ipv4);
+        candidates
+            .first()
+            .map_or(report.ip_address.as_str(), |address| {
+                address.address.as_str()
+            })
+    }
+}
+
+/// Label for an address scope, as used by the API
+pub fn address_scope_label(scope: AddressScope) -> &'static str {
+    match scope {
+        AddressScope::Lan => "lan",
+        AddressScope::Tailscale => "tailscale",
+        AddressScope::Public => "public",
+        AddressScope::LinkLocal => "link-local",
+        AddressScope::Unspecified => "unknown",
+    }
+}
+
+/// Label for a check status, as used by the API and alerts
+pub fn check_status_label(status: CheckStatus) -> &'static str {
+    match status {
+        CheckStatus::Ok => "ok",
+        CheckStatus::Warning => "warning",
+        CheckStatus::Critical => "critical",
+        CheckStatus::Unknown => "unknown",
+    }
+}
+
+/// Formats a transfer rate in the configured unit system, e.g. "1.2 MiB/s"
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", format_bytes(bytes_per_sec.round() as u64))
+}
+
+/// Formats a duration in seconds as a compact uptime, e.g. "3d 4h" or "12m"
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours) {
+        (0, 0) if minutes == 0 => format!("{seconds}s"),
+        (0, 0) => format!("{minutes}m"),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h"),
+    }
+}
+
+/// Whether a service status means it is up, for containers and systemd units alike
+pub fn service_is_up(status: &str) -> bool {
+    matches!(status, "running" | "active")
+}
+
 /// Gets current Unix timestamp in seconds
 fn current_timestamp() -> u64 {
     SystemTime::now()
@@ -63,19 +1064,73 @@ impl App {
         Self {
             reports: HashMap::new(),
             report_order: Vec::new(),
-            selected_client_index: 0,
+            disk_thresholds: Arc::new(DiskThresholds::from_env()),
+            alert_rules: Arc::new(AlertRules::from_env()),
+            inventories: HashMap::new(),
+            site: std::env::var("SWARM_SITE")
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            children: Vec::new(),
+            ha: None,
+            metrics: StateMetrics::default(),
         }
     }
 
-    pub fn update_report(&mut self, report: ReportEntry) {
-        let key = format!("{}:{}", report.report.hostname, report.report.ip_address);
+    pub fn update_report(&mut self, mut report: ReportEntry) {
+        if report.report.site.is_empty() {
+            report.report.site = self.site.clone();
+        }
+        // Kept only if the report is accepted, so a duplicate or late report
+        // can't replace a newer inventory
+        let inventory = report
+            .report
+            .inventory
+            .take()
+            .map(|inventory| ReceivedInventory {
+                inventory,
+                received_at: report.last_updated,
+            });
+
+        let key = report.node_key().to_string();
 
         if let Some(existing) = self.reports.get_mut(&key) {
-            existing.report = report.report;
-            existing.last_updated = report.last_updated;
+            // The entry may still be shared with a published snapshot, so
+            // it is replaced rather than changed in place
+            let mut stream = existing.stream.clone();
+            let arrival = if report.second_hand() {
+                stream.follow(&report.report, report.received_at)
+            } else {
+                stream.observe(&report.report, report.received_at)
+            };
+            let last_updated = existing.last_updated.max(report.last_updated);
+
+            // Reports replayed after an outage arrive oldest first; one older
+            // than what is shown already must not replace it
+            if arrival != Arrival::Next || report.report.collected_at < existing.report.collected_at
+            {
+                let existing = Arc::make_mut(existing);
+                existing.stream = stream;
+                existing.last_updated = last_updated;
+                return;
+            }
+            report.stream = stream;
+            report.last_updated = last_updated;
+            if let Some(inventory) = inventory {
+                self.inventories.insert(key.clone(), Arc::new(inventory));
+            }
+            *existing = Arc::new(report);
         } else {
+            if report.second_hand() {
+                report.stream.follow(&report.report, report.received_at);
+            } else {
+                report.stream.observe(&report.report, report.received_at);
+            }
+            if let Some(inventory) = inventory {
+                self.inventories.insert(key.clone(), Arc::new(inventory));
+            }
             self.report_order.push(key.clone());
-            self.reports.insert(key, report);
+            self.reports.insert(key, Arc::new(report));
         }
     }
 
@@ -92,55 +1147,29 @@ impl App {
 
         for key in keys_to_remove {
             self.reports.remove(&key);
+            self.inventories.remove(&key);
             self.report_order.retain(|k| k != &key);
         }
-
-        // Validate selection after removing clients
-        self.validate_selection();
     }
 
-    pub fn get_ordered_reports(&self) -> Vec<&ReportEntry> {
+    /// Reports in the order their nodes first reported
+    pub fn reports_in_arrival_order(&self) -> Vec<&ReportEntry> {
         self.report_order
             .iter()
             .filter_map(|key| self.reports.get(key))
+            .map(Arc::as_ref)
             .collect()
     }
 
-    /// Navigate to the next client
-    pub fn select_next_client(&mut self) {
-        if !self.report_order.is_empty() {
-            self.selected_client_index = (self.selected_client_index + 1) % self.report_order.len();
-        }
-    }
-
-    /// Navigate to the previous client
-    pub fn select_previous_client(&mut self) {
-        if !self.report_order.is_empty() {
-            self.selected_client_index = if self.selected_client_index == 0 {
-                self.report_order.len() - 1
-            } else {
-                self.selected_client_index - 1
-            };
-        }
-    }
-
-    /// Get the currently selected client report
-    pub fn get_selected_client(&self) -> Option<&ReportEntry> {
-        if self.selected_client_index < self.report_order.len() {
-            let key = &self.report_order[self.selected_client_index];
-            self.reports.get(key)
-        } else {
-            None
-        }
-    }
-
-    /// Ensure selected index is valid after reports change
-    pub fn validate_selection(&mut self) {
-        if self.selected_client_index >= self.report_order.len() && !self.report_order.is_empty() {
-            self.selected_client_index = self.report_order.len() - 1;
-        } else if self.report_order.is_empty() {
-            self.selected_client_index = 0;
-        }
+    /// Reports grouped by site with this sentinel's own site first, in
+    /// arrival order within a site
+    pub fn get_ordered_reports(&self) -> Vec<&ReportEntry> {
+        let mut reports = self.reports_in_arrival_order();
+        reports.sort_by(|a, b| {
+            (a.report.site != self.site, &a.report.site)
+                .cmp(&(b.report.site != self.site, &b.report.site))
+        });
+        reports
     }
 
     /// Converts internal reports to web-friendly format with status indicators
@@ -167,11 +1196,117 @@ impl App {
                         name: s.name.clone(),
                         status: s.status.clone(),
                         needs_update: s.needs_update,
+                        image: s.image.clone(),
+                        kind: service_kind_label(s.kind()).to_string(),
+                        sub_state: s.sub_state.clone(),
+                        restart_count: s.restart_count,
+                        since: s.since,
+                        health: service_health_label(s.health()).to_string(),
+                        started_at: s.started_at,
+                        version: s.version.clone(),
+                        ports: s.ports.clone(),
+                        labels: s.labels.clone(),
+                        probes: s.probes.iter().map(WebCheck::from).collect(),
+                    })
+                    .collect();
+
+                let disks = entry
+                    .report
+                    .disks
+                    .iter()
+                    .map(|disk| {
+                        let usage_percent = percent(disk.used_bytes, disk.total_bytes);
+                        WebDisk {
+                            mount_point: disk.mount_point.clone(),
+                            device: disk.device.clone(),
+                            file_system: disk.file_system.clone(),
+                            total_bytes: disk.total_bytes,
+                            used_bytes: disk.used_bytes,
+                            available_bytes: disk.available_bytes,
+                            usage_percent,
+                            inode_percent: percent(disk.used_inodes, disk.total_inodes),
+                            removable: disk.removable,
+                            level: self
+                                .disk_thresholds
+                                .level(&disk.mount_point, usage_percent)
+                                .to_string(),
+                        }
+                    })
+                    .collect();
+
+                let network_interfaces = entry
+                    .report
+                    .network_interfaces
+                    .iter()
+                    .map(|interface| WebNetworkInterface {
+                        name: interface.name.clone(),
+                        up: interface.up,
+                        addresses: interface.addresses.clone(),
+                        mac_address: interface.mac_address.clone(),
+                        mtu: interface.mtu,
+                        rx_bytes_per_sec: interface.rx_bytes_per_sec,
+                        tx_bytes_per_sec: interface.tx_bytes_per_sec,
+                        rx_packets_per_sec: interface.rx_packets_per_sec,
+                        tx_packets_per_sec: interface.tx_packets_per_sec,
+                        rx_errors: interface.rx_errors,
+                        tx_errors: interface.tx_errors,
+                        rx_dropped: interface.rx_dropped,
+                        tx_dropped: interface.tx_dropped,
+                    })
+                    .collect();
+
+                let cpu = entry.report.cpu.clone().unwrap_or_default();
+                let cpu = WebCpu {
+                    load_1: cpu.load_1,
+                    load_5: cpu.load_5,
+                    load_15: cpu.load_15,
+                    boot_time: cpu.boot_time,
+                    uptime_secs: cpu.uptime_secs,
+                    physical_cores: cpu.physical_cores,
+                    core_usage: cpu.core_usage,
+                    iowait_percent: cpu.iowait_percent,
+                    steal_percent: cpu.steal_percent,
+                    brand: cpu.brand,
+                };
+
+                let memory = entry.report.memory.as_ref().map(|memory| WebMemory {
+                    total_bytes: memory.total_bytes,
+                    used_bytes: memory.used_bytes,
+                    available_bytes: memory.available_bytes,
+                    free_bytes: memory.free_bytes,
+                    cached_bytes: memory.cached_bytes,
+                    buffers_bytes: memory.buffers_bytes,
+                    swap_total_bytes: memory.swap_total_bytes,
+                    swap_used_bytes: memory.swap_used_bytes,
+                    usage_percent: percent(memory.used_bytes, memory.total_bytes),
+                    swap_percent: percent(memory.swap_used_bytes, memory.swap_total_bytes),
+                    pressure: memory.pressure.as_ref().map(|pressure| WebMemoryPressure {
+                        some_avg10: pressure.some_avg10,
+                        some_avg60: pressure.some_avg60,
+                        some_avg300: pressure.some_avg300,
+                        full_avg10: pressure.full_avg10,
+                        full_avg60: pressure.full_avg60,
+                        full_avg300: pressure.full_avg300,
+                    }),
+                });
+
+                let temperatures = entry
+                    .report
+                    .temperatures
+                    .iter()
+                    .map(|temperature| WebTemperature {
+                        label: temperature.label.clone(),
+                        current_celsius: temperature.current_celsius,
+                        max_celsius: temperature.max_celsius,
+                        critical_celsius: self.alert_rules.temperature.critical(temperature),
+                        level: self.alert_rules.temperature.level(temperature).to_string(),
                     })
                     .collect();
 
                 WebClient {
                     hostname: entry.report.hostname.clone(),
+                    site: entry.report.site.clone(),
+                    relayed_by: entry.relayed_by.clone(),
                     ip_address: entry.report.ip_address.clone(),
                     node_id: entry.report.node_id.clone(),
                     cpu_usage: parse_cpu_usage(&entry.report.cpu_usage),
@@ -181,8 +1316,567 @@ impl App {
                     seconds_since_update,
                     status,
                     services,
+                    disks,
+                    network_interfaces,
+                    cpu,
+                    memory,
+                    temperatures,
+                    top_cpu_processes: entry
+                        .report
+                        .top_cpu_processes
+                        .iter()
+                        .map(WebProcess::from)
+                        .collect(),
+                    top_memory_processes: entry
+                        .report
+                        .top_memory_processes
+                        .iter()
+                        .map(WebProcess::from)
+                        .collect(),
+                    checks: entry.report.checks.iter().map(WebCheck::from).collect(),
+                    custom_metrics: entry
+                        .report
+                        .custom_metrics
+                        .iter()
+                        .map(|metric| WebMetric {
+                            name: metric.name.clone(),
+                            value: metric.value,
+                            unit: metric.unit.clone(),
+                        })
+                        .collect(),
+                    tailscale: entry.report.tailscale.as_ref().map(WebTailscale::from),
+                    addresses: entry
+                        .report
+                        .addresses
+                        .iter()
+                        .map(WebAddress::from)
+                        .collect(),
+                    stream: WebReportStream {
+                        sequence: entry.report.sequence,
+                        duplicates: entry.stream.duplicates,
+                        out_of_order: entry.stream.out_of_order,
+                        missed: entry.stream.missed,
+                        clock_skew_ms: entry.stream.clock_skew_ms(),
+                        clock_drift: self.alert_rules.clock.drifting(&entry.stream),
+                    },
                 }
             })
             .collect()
     }
+
+    /// Lists the host inventory of every reporting node
+    pub fn get_inventory(&self) -> Vec<WebInventory> {
+        self.get_ordered_reports()
+            .iter()
+            .map(|entry| {
+                let report = &entry.report;
+                let received = self.inventories.get(entry.node_key());
+                let inventory = received.map(|r| r.inventory.clone()).unwrap_or_default();
+
+                WebInventory {
+                    hostname: report.hostname.clone(),
+                    node_id: report.node_id.clone(),
+                    ip_address: report.ip_address.clone(),
+                    os_name: inventory.os_name,
+                    os_version: inventory.os_version,
+                    distribution_id: inventory.distribution_id,
+                    kernel_version: inventory.kernel_version,
+                    architecture: inventory.architecture,
+                    cpu_model: inventory.cpu_model,
+                    logical_cores: inventory.logical_cores,
+                    physical_cores: inventory.physical_cores,
+                    total_memory_bytes: inventory.total_memory_bytes,
+                    virtualization: inventory.virtualization,
+                    container: inventory.container,
+                    reporter_version: inventory.reporter_version,
+                    received_at: received.map_or(0, |r| r.received_at),
+                }
+            })
+            .collect()
+    }
+
+    /// Lists pending package updates per node, nodes needing attention first
+    pub fn get_patch_status(&self) -> Vec<WebPatchStatus> {
+        let mut status: Vec<WebPatchStatus> = self
+            .get_ordered_reports()
+            .iter()
+            .filter_map(|entry| {
+                let updates = entry.report.package_updates.as_ref()?;
+                Some(WebPatchStatus {
+                    hostname: entry.report.hostname.clone(),
+                    node_id: entry.report.node_id.clone(),
+                    manager: updates.manager.clone(),
+                    pending: updates.pending,
+                    security: updates.security,
+                    reboot_required: updates.reboot_required,
+                    packages: updates.packages.clone(),
+                    checked_at: updates.checked_at,
+                })
+            })
+            .collect();
+
+        status.sort_by_key(|node| {
+            std::cmp::Reverse((node.security, node.reboot_required, node.pending))
+        });
+        status
+    }
+
+    /// Lists the certificates watched across the fleet, soonest to expire first
+    pub fn get_certificates(&self) -> Vec<WebCertificate> {
+        let now = current_timestamp() as i64;
+
+        let mut certificates: Vec<WebCertificate> = self
+            .get_ordered_reports()
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .report
+                    .certificates
+                    .iter()
+                    .map(|certificate| WebCertificate {
+                        hostname: entry.report.hostname.clone(),
+                        node_id: entry.report.node_id.clone(),
+                        source: certificate.source.clone(),
+                        subject: certificate.subject.clone(),
+                        issuer: certificate.issuer.clone(),
+                        sans: certificate.sans.clone(),
+                        not_after: certificate.not_after,
+                        days_remaining: if certificate.error.is_empty() {
+                            CertificateRule::days_remaining(certificate, now)
+                        } else {
+                            0
+                        },
+                        level: self
+                            .alert_rules
+                            .certificate
+                            .level(certificate, now)
+                            .to_string(),
+                        error: certificate.error.clone(),
+                        checked_at: certificate.checked_at,
+                    })
+            })
+            .collect();
+
+        // Unreadable certificates first, then by expiry
+        certificates
+            .sort_by_key(|certificate| (certificate.error.is_empty(), certificate.days_remaining));
+        certificates
+    }
+
+    /// Lists the machines in every reported tailnet, those without a reporter first
+    pub fn get_tailnet_peers(&self) -> Vec<WebTailnetPeer> {
+        let reports = self.get_ordered_reports();
+        let mut peers: HashMap<String, WebTailnetPeer> = HashMap::new();
+
+        for entry in &reports {
+            let Some(status) = &entry.report.tailscale else {
+                continue;
+            };
+
+            for peer in &status.peers {
+                let key = if peer.dns_name.is_empty() {
+                    &peer.hostname
+                } else {
+                    &peer.dns_name
+                };
+                let merged = peers.entry(key.clone()).or_insert_with(|| WebTailnetPeer {
+                    tailnet: status.tailnet.clone(),
+                    hostname: peer.hostname.clone(),
+                    dns_name: peer.dns_name.clone(),
+                    addresses: peer.addresses.clone(),
+                    os: peer.os.clone(),
+                    online: false,
+                    offers_exit_node: peer.offers_exit_node,
+                    last_seen: 0,
+                    key_expiry: peer.key_expiry,
+                    reporter: None,
+                    seen_by: Vec::new(),
+                });
+                merged.online |= peer.online;
+                merged.last_seen = merged.last_seen.max(peer.last_seen);
+                merged.seen_by.push(entry.report.hostname.clone());
+            }
+
+            // The reporter itself, so a tailnet with a single reporter is listed too
+            let key = if status.dns_name.is_empty() {
+                &entry.report.hostname
+            } else {
+                &status.dns_name
+            };
+            let own = peers.entry(key.clone()).or_insert_with(|| WebTailnetPeer {
+                tailnet: status.tailnet.clone(),
+                hostname: entry.report.hostname.clone(),
+                dns_name: status.dns_name.clone(),
+                addresses: status.addresses.clone(),
+                os: String::new(),
+                online: true,
+                offers_exit_node: status.offers_exit_node,
+                last_seen: 0,
+                key_expiry: status.key_expiry,
+                reporter: None,
+                seen_by: Vec::new(),
+            });
+            own.online = true;
+            own.reporter = Some(entry.report.node_id.clone());
+        }
+
+        // Reporters without Tailscale status may still be reachable over the tailnet
+        for peer in peers.values_mut().filter(|peer| peer.reporter.is_none()) {
+            peer.reporter = reports
+                .iter()
+                .find(|entry| {
+                    peer.addresses.contains(&entry.report.ip_address)
+                        || entry
+                            .report
+                            .addresses
+                            .iter()
+                            .any(|address| peer.addresses.contains(&address.address))
+                })
+                .map(|entry| entry.report.node_id.clone());
+        }
+
+        let mut peers: Vec<WebTailnetPeer> = peers.into_values().collect();
+        peers.sort_by(|a, b| {
+            (a.reporter.is_some(), !a.online, &a.dns_name).cmp(&(
+                b.reporter.is_some(),
+                !b.online,
+                &b.dns_name,
+            ))
+        });
+        peers
+    }
+
+    /// Lists this sentinel's own site and the sites reached through each
+    /// child sentinel, with their node counts
+    pub fn get_sites(&self) -> Vec<WebSite> {
+        let current_time = current_timestamp();
+        // Node and stale node counts per (child sentinel, site)
+        let mut counts: HashMap<(Option<&str>, &str), (usize, usize)> = HashMap::new();
+        for entry in self.reports.values() {
+            let count = counts
+                .entry((entry.relayed_by.as_deref(), entry.report.site.as_str()))
+                .or_default();
+            count.0 += 1;
+            if current_time.saturating_sub(entry.last_updated) > 30 {
+                count.1 += 1;
+            }
+        }
+
+        let mut sites = vec![WebSite {
+            site: self.site.clone(),
+            sentinel: "local".to_string(),
+            state: "local".to_string(),
+            nodes: 0,
+            stale_nodes: 0,
+            last_contact: current_time,
+            error: String::new(),
+        }];
+        for ((relayed_by, site), (nodes, stale_nodes)) in &counts {
+            let Some(child) = relayed_by else {
+                sites[0].nodes += nodes;
+                sites[0].stale_nodes += stale_nodes;
+                continue;
+            };
+            let status = self.children.iter().find(|c| c.address == *child);
+            sites.push(WebSite {
+                site: site.to_string(),
+                sentinel: child.to_string(),
+                state: status
+                    .map_or("unreachable", |c| c.state.label())
+                    .to_string(),
+                nodes: *nodes,
+                stale_nodes: *stale_nodes,
+                last_contact: status.map_or(0, |c| c.last_contact),
+                error: status.map(|c| c.last_error.clone()).unwrap_or_default(),
+            });
+        }
+
+        // Children that have no nodes, e.g. because they were never reached
+        for child in &self.children {
+            if !sites.iter().any(|site| site.sentinel == child.address) {
+                sites.push(WebSite {
+                    site: child.site.clone(),
+                    sentinel: child.address.clone(),
+                    state: child.state.label().to_string(),
+                    nodes: 0,
+                    stale_nodes: 0,
+                    last_contact: child.last_contact,
+                    error: child.last_error.clone(),
+                });
+            }
+        }
+
+        sites[1..].sort_by(|a, b| a.site.cmp(&b.site));
+        sites
+    }
+
+    /// Whether the child sentinel a node is seen through can't be reached
+    pub fn relay_unreachable(&self, entry: &ReportEntry) -> bool {
+        entry.relayed_by.as_ref().is_some_and(|child| {
+            self.children
+                .iter()
+                .any(|c| &c.address == child && c.state == ChildState::Unreachable)
+        })
+    }
+
+    /// Every node's latest report as served to parent sentinels, with its
+    /// inventory and the time it was received filled back in
+    pub fn relayable_reports(&self) -> Vec<SystemReport> {
+        self.get_ordered_reports()
+            .iter()
+            .map(|entry| {
+                let mut report = entry.report.clone();
+                report.received_at = entry.received_at;
+                report.inventory = self
+                    .inventories
+                    .get(entry.node_key())
+                    .map(|received| received.inventory.clone());
+                report
+            })
+            .collect()
+    }
+
+    /// Evaluates the alert rules against every node's latest report
+    pub fn get_alerts(&self) -> Vec<Alert> {
+        self.get_ordered_reports()
+            .iter()
+            .flat_map(|entry| self.alert_rules.evaluate(entry))
+            .collect()
+    }
+
+    /// Lists containers across the fleet whose image has a newer registry digest
+    pub fn get_outdated_services(&self) -> Vec<OutdatedService> {
+        self.get_ordered_reports()
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .report
+                    .services
+                    .iter()
+                    .filter(|s| s.needs_update)
+                    .map(|s| OutdatedService {
+                        hostname: entry.report.hostname.clone(),
+                        node_id: entry.report.node_id.clone(),
+                        name: s.name.clone(),
+                        image: s.image.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Lists Docker Swarm clusters, using the freshest report when several
+    /// managers of the same cluster run a reporter
+    pub fn get_swarm_clusters(&self) -> Vec<WebSwarmCluster> {
+        let mut clusters: Vec<WebSwarmCluster> = Vec::new();
+
+        for entry in self.get_ordered_reports() {
+            let Some(swarm) = &entry.report.docker_swarm else {
+                continue;
+            };
+
+            if let Some(existing) = clusters.iter().find(|c| c.cluster_id == swarm.cluster_id)
+                && existing.last_updated >= entry.last_updated
+            {
+                continue;
+            }
+            clusters.retain(|c| c.cluster_id != swarm.cluster_id);
+
+            let services = swarm
+                .services
+                .iter()
+                .map(|service| WebSwarmService {
+                    name: service.name.clone(),
+                    image: service.image.clone(),
+                    mode: service.mode.clone(),
+                    desired_replicas: service.desired_replicas,
+                    running_replicas: service.running_replicas,
+                    under_replicated: service.running_replicas < service.desired_replicas,
+                    tasks: service
+                        .tasks
+                        .iter()
+                        .map(|task| WebSwarmTask {
+                            name: task.name.clone(),
+                            node: task.node.clone(),
+                            state: task.state.clone(),
+                            error: task.error.clone(),
+                        })
+                        .collect(),
+                })
+                .collect();
+
+            let nodes = swarm
+                .nodes
+                .iter()
+                .map(|node| WebSwarmNode {
+                    hostname: node.hostname.clone(),
+                    status: node.status.clone(),
+                    availability: node.availability.clone(),
+                    manager_status: node.manager_status.clone(),
+                    engine_version: node.engine_version.clone(),
+                })
+                .collect();
+
+            clusters.push(WebSwarmCluster {
+                cluster_id: swarm.cluster_id.clone(),
+                reported_by: entry.report.hostname.clone(),
+                last_updated: entry.last_updated,
+                services,
+                nodes,
+            });
+        }
+
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(node_id: &str, sequence: u64, collected_at: i64) -> SystemReport {
+        SystemReport {
+            node_id: node_id.to_string(),
+            hostname: node_id.to_string(),
+            reporter_started_at: 1,
+            sequence,
+            collected_at,
+            ..Default::default()
+        }
+    }
+
+    fn inventory(os_version: &str) -> HostInventory {
+        HostInventory {
+            os_version: os_version.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn from_run(started_at: i64, sequence: u64, sent_at: i64) -> SystemReport {
+        SystemReport {
+            reporter_started_at: started_at,
+            sequence,
+            sent_at,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stream_in_order() {
+        let mut stream = ReportStream::default();
+        for sequence in 1..=3 {
+            let arrival = stream.observe(&from_run(100, sequence, 0), 0);
+            assert_eq!(arrival, Arrival::Next);
+        }
+        assert_eq!(stream.last_sequence, 3);
+        assert_eq!(
+            (stream.duplicates, stream.out_of_order, stream.missed),
+            (0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn stream_duplicate() {
+        let mut stream = ReportStream::default();
+        stream.observe(&from_run(100, 1, 0), 0);
+        assert_eq!(stream.observe(&from_run(100, 1, 0), 0), Arrival::Duplicate);
+        assert_eq!(stream.duplicates, 1);
+        assert_eq!(stream.last_sequence, 1);
+    }
+
+    #[test]
+    fn stream_reordered_and_gaps() {
+        let mut stream = ReportStream::default();
+        stream.observe(&from_run(100, 1, 0), 0);
+        // 2 and 3 are delayed, 4 arrives first
+        assert_eq!(stream.observe(&from_run(100, 4, 0), 0), Arrival::Next);
+        assert_eq!(stream.missed, 2);
+        assert_eq!(stream.observe(&from_run(100, 2, 0), 0), Arrival::OutOfOrder);
+        assert_eq!(stream.out_of_order, 1);
+        assert_eq!(stream.last_sequence, 4);
+    }
+
+    #[test]
+    fn stream_reporter_restart() {
+        let mut stream = ReportStream::default();
+        stream.observe(&from_run(100, 7, 0), 0);
+        // A restarted reporter counts from 1 again; 2 means one was lost
+        assert_eq!(stream.observe(&from_run(200, 2, 0), 0), Arrival::Next);
+        assert_eq!((stream.reporter_started_at, stream.last_sequence), (200, 2));
+        assert_eq!(stream.missed, 1);
+        // Late reports from the previous run are out of order
+        assert_eq!(stream.observe(&from_run(100, 8, 0), 0), Arrival::OutOfOrder);
+    }
+
+    #[test]
+    fn stream_without_sequence_numbers_is_always_next() {
+        let mut stream = ReportStream::default();
+        assert_eq!(stream.observe(&from_run(0, 0, 0), 0), Arrival::Next);
+        assert_eq!(stream.observe(&from_run(0, 0, 0), 0), Arrival::Next);
+        assert_eq!(stream.duplicates, 0);
+    }
+
+    #[test]
+    fn stream_clock_skew_is_the_median() {
+        let mut stream = ReportStream::default();
+        assert_eq!(stream.clock_skew_ms(), None);
+        // Node clock 5s ahead, with one outlier from a slow delivery
+        for (sequence, skew) in [(1, 5_000), (2, 5_010), (3, -40_000), (4, 4_990), (5, 5_000)] {
+            stream.observe(&from_run(100, sequence, 1_000_000 + skew), 1_000_000);
+        }
+        assert_eq!(stream.clock_skew_ms(), Some(5_000));
+    }
+
+    #[test]
+    fn relayed_stream_ignores_repeats_without_counting() {
+        let mut stream = ReportStream::default();
+        assert_eq!(stream.follow(&from_run(100, 5, 0), 0), Arrival::Next);
+        assert_eq!(stream.follow(&from_run(100, 5, 0), 0), Arrival::Duplicate);
+        assert_eq!(stream.follow(&from_run(100, 9, 0), 0), Arrival::Next);
+        assert_eq!((stream.duplicates, stream.missed), (0, 0));
+    }
+
+    #[test]
+    fn late_reports_do_not_replace_the_inventory() {
+        let mut app = App::new();
+        let mut first = report("node", 1, 1_000);
+        first.inventory = Some(inventory("12"));
+        let mut second = report("node", 2, 2_000);
+        second.inventory = Some(inventory("13"));
+        app.update_report(ReportEntry::received(second));
+
+        let mut duplicate = report("node", 2, 2_000);
+        duplicate.inventory = Some(inventory("old"));
+        app.update_report(ReportEntry::received(duplicate));
+        app.update_report(ReportEntry::received(first));
+
+        assert_eq!(app.inventories["node"].inventory.os_version, "13");
+    }
+
+    #[test]
+    fn offline_nodes_lose_their_inventory() {
+        let mut app = App::new();
+        let mut entry = ReportEntry::received(report("node", 1, 1_000));
+        entry.report.inventory = Some(inventory("12"));
+        entry.last_updated -= 120;
+        app.update_report(entry);
+        assert!(app.inventories.contains_key("node"));
+
+        app.remove_offline_clients(60);
+        assert!(app.reports.is_empty());
+        assert!(app.inventories.is_empty());
+    }
+
+    #[test]
+    fn nodes_without_a_node_id_keep_their_inventory() {
+        let mut app = App::new();
+        let mut entry = ReportEntry::received(report("", 1, 1_000));
+        entry.report.hostname = "legacy-host".to_string();
+        entry.report.inventory = Some(inventory("12"));
+        app.update_report(entry);
+
+        assert!(app.inventories.contains_key("legacy-host"));
+        assert_eq!(app.get_inventory()[0].os_version, "12");
+        let relayed = app.relayable_reports();
+        assert_eq!(relayed[0].inventory.as_ref().unwrap().os_version, "12");
+    }
 }
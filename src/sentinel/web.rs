@@ -1,4 +1,5 @@
-use super::types::SharedState;
+use super::state::{StateEvent, StateHandle};
+use super::types::{AddressPreference, ByteUnits, WebInventory};
 /// Web server for SwarmReport Sentinel
 ///
 /// Provides a REST API and web dashboard for viewing system reports from
@@ -8,47 +9,434 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use warp::Filter;
 
 /// API endpoint to get all connected clients and their current status
-async fn get_clients_api(state: SharedState) -> Result<impl warp::Reply, warp::Rejection> {
-    let app = state.lock().unwrap();
+async fn get_clients_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
     Ok(warp::reply::json(&app.get_web_clients()))
 }
 
+/// API endpoint to list containers whose image has an update available
+async fn get_outdated_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_outdated_services()))
+}
+
+/// API endpoint to get Docker Swarm clusters reported by manager nodes
+async fn get_swarm_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_swarm_clusters()))
+}
+
+/// API endpoint listing pending OS package updates per node
+async fn get_patches_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_patch_status()))
+}
+
+/// API endpoint listing the TLS certificates watched across the fleet
+async fn get_certificates_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_certificates()))
+}
+
+/// API endpoint listing the machines in every reported tailnet
+async fn get_tailnet_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_tailnet_peers()))
+}
+
+/// API endpoint listing this sentinel's site and those of its child sentinels
+async fn get_sites_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_sites()))
+}
+
+/// API endpoint describing the high availability pairing, null when unpaired
+async fn get_ha_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.ha))
+}
+
+/// API endpoint showing how far the state actor is behind and how old the
+/// snapshot the API serves is
+async fn get_state_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as i64);
+    Ok(warp::reply::json(&serde_json::json!({
+        "nodes": app.reports.len(),
+        "snapshot_age_ms": now.saturating_sub(app.metrics.published_at),
+        "metrics": app.metrics,
+    })))
+}
+
+/// API endpoint listing the host inventory of every node
+async fn get_inventory_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_inventory()))
+}
+
+/// Quotes a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// API endpoint exporting the host inventory as a CSV download
+async fn get_inventory_csv(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let inventory = state.snapshot().get_inventory();
+
+    let mut csv = WebInventory::CSV_HEADER.join(",");
+    csv.push('\n');
+    for row in &inventory {
+        let fields: Vec<String> = row.csv_fields().iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    Ok(warp::reply::with_header(
+        warp::reply::with_header(csv, "Content-Type", "text/csv; charset=utf-8"),
+        "Content-Disposition",
+        "attachment; filename=\"inventory.csv\"",
+    ))
+}
+
+/// API endpoint listing the alerts currently firing across the fleet
+async fn get_alerts_api(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
+    let app = state.snapshot();
+    Ok(warp::reply::json(&app.get_alerts()))
+}
+
+/// API endpoint exposing display settings shared with the TUI
+async fn get_settings_api() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&serde_json::json!({
+        "byte_units": ByteUnits::get().label(),
+        "display_address": AddressPreference::from_env().label(),
+    })))
+}
+
 /// API endpoint to add a test client for demonstration purposes
-async fn add_test_client(state: SharedState) -> Result<impl warp::Reply, warp::Rejection> {
+async fn add_test_client(state: StateHandle) -> Result<impl warp::Reply, warp::Rejection> {
     use super::types::ReportEntry;
-    use crate::swarmreport::{Service, SystemReport};
+    use crate::swarmreport::{
+        AddressFamily, AddressScope, Certificate, CheckResult, CheckStatus, CpuInfo, CustomMetric,
+        DiskInfo, HostInventory, IpAddress, MemoryInfo, MemoryPressure, NetworkInterface,
+        PackageUpdates, ProcessInfo, Service, ServiceHealth, ServiceKind, SystemReport,
+        TailscalePeer, TailscaleStatus, Temperature,
+    };
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
 
     let test_report = SystemReport {
         node_id: "test-node-123".to_string(),
+        collected_at: timestamp as i64 * 1000,
+        sent_at: timestamp as i64 * 1000,
+        // Without a sequence number the test client is never taken for a duplicate
+        sequence: 0,
+        reporter_started_at: 0,
+        // Filled in with this sentinel's site when received
+        site: String::new(),
+        received_at: 0,
         hostname: "test-host".to_string(),
         ip_address: "192.168.1.100".to_string(),
         cpu_usage: "45.5%".to_string(),
-        memory_usage: "8.5/16 GB".to_string(),
+        memory_usage: "8.5 GiB / 16.0 GiB".to_string(),
+        memory: Some(MemoryInfo {
+            total_bytes: 16 << 30,
+            used_bytes: 8704 << 20,
+            available_bytes: 7 << 30,
+            free_bytes: 2 << 30,
+            cached_bytes: 4 << 30,
+            buffers_bytes: 256 << 20,
+            swap_total_bytes: 4 << 30,
+            swap_used_bytes: 512 << 20,
+            pressure: Some(MemoryPressure {
+                some_avg10: 1.5,
+                some_avg60: 0.8,
+                some_avg300: 0.3,
+                ..Default::default()
+            }),
+        }),
         disk_usage: "120.5 GB / 500.2 GB".to_string(),
+        docker_swarm: None,
+        tailscale: Some(TailscaleStatus {
+            backend_state: "Running".to_string(),
+            tailnet: "example.com".to_string(),
+            dns_name: "test-host.tail1234.ts.net".to_string(),
+            addresses: vec!["100.64.0.10".to_string(), "fd7a:115c:a1e0::a".to_string()],
+            offers_exit_node: false,
+            exit_node: String::new(),
+            key_expiry: timestamp as i64 + 120 * 86_400,
+            peers: vec![
+                TailscalePeer {
+                    hostname: "nas".to_string(),
+                    dns_name: "nas.tail1234.ts.net".to_string(),
+                    addresses: vec!["100.64.0.20".to_string()],
+                    os: "linux".to_string(),
+                    online: true,
+                    offers_exit_node: true,
+                    last_seen: 0,
+                    key_expiry: 0,
+                },
+                TailscalePeer {
+                    hostname: "phone".to_string(),
+                    dns_name: "phone.tail1234.ts.net".to_string(),
+                    addresses: vec!["100.64.0.30".to_string()],
+                    os: "iOS".to_string(),
+                    online: false,
+                    offers_exit_node: false,
+                    last_seen: timestamp as i64 - 7200,
+                    key_expiry: timestamp as i64 + 30 * 86_400,
+                },
+            ],
+        }),
+        cpu: Some(CpuInfo {
+            load_1: 2.4,
+            load_5: 1.8,
+            load_15: 1.2,
+            boot_time: timestamp - 3 * 86400,
+            uptime_secs: 3 * 86400,
+            physical_cores: 2,
+            core_usage: vec![62.0, 38.5, 51.0, 30.5],
+            iowait_percent: 4.2,
+            steal_percent: 0.3,
+            brand: "Intel(R) Xeon(R) CPU".to_string(),
+        }),
+        disks: vec![
+            DiskInfo {
+                mount_point: "/".to_string(),
+                device: "/dev/sda1".to_string(),
+                file_system: "ext4".to_string(),
+                total_bytes: 100 << 30,
+                used_bytes: 42 << 30,
+                available_bytes: 53 << 30,
+                total_inodes: 6_553_600,
+                used_inodes: 412_000,
+                removable: false,
+            },
+            DiskInfo {
+                mount_point: "/var".to_string(),
+                device: "/dev/sda2".to_string(),
+                file_system: "ext4".to_string(),
+                total_bytes: 400 << 30,
+                used_bytes: 371 << 30,
+                available_bytes: 9 << 30,
+                total_inodes: 26_214_400,
+                used_inodes: 2_100_000,
+                removable: false,
+            },
+        ],
+        network_interfaces: vec![NetworkInterface {
+            name: "eth0".to_string(),
+            up: true,
+            addresses: vec!["192.168.1.100/24".to_string()],
+            mac_address: "52:54:00:12:34:56".to_string(),
+            mtu: 1500,
+            rx_bytes_per_sec: 1_250_000.0,
+            tx_bytes_per_sec: 310_000.0,
+            rx_packets_per_sec: 900.0,
+            tx_packets_per_sec: 450.0,
+            rx_dropped: 12,
+            ..Default::default()
+        }],
+        addresses: vec![
+            IpAddress {
+                address: "192.168.1.100".to_string(),
+                prefix_length: 24,
+                interface: "eth0".to_string(),
+                family: AddressFamily::Ipv4.into(),
+                scope: AddressScope::Lan.into(),
+            },
+            IpAddress {
+                address: "2001:db8::100".to_string(),
+                prefix_length: 64,
+                interface: "eth0".to_string(),
+                family: AddressFamily::Ipv6.into(),
+                scope: AddressScope::Public.into(),
+            },
+            IpAddress {
+                address: "100.64.0.10".to_string(),
+                prefix_length: 32,
+                interface: "tailscale0".to_string(),
+                family: AddressFamily::Ipv4.into(),
+                scope: AddressScope::Tailscale.into(),
+            },
+        ],
+        temperatures: vec![
+            Temperature {
+                label: "coretemp Package id 0".to_string(),
+                current_celsius: 84.0,
+                max_celsius: 88.0,
+                critical_celsius: 90.0,
+            },
+            Temperature {
+                label: "nvme Composite".to_string(),
+                current_celsius: 41.0,
+                max_celsius: 47.0,
+                critical_celsius: 0.0,
+            },
+        ],
+        top_cpu_processes: vec![
+            ProcessInfo {
+                pid: 2314,
+                name: "postgres".to_string(),
+                user: "postgres".to_string(),
+                cpu_percent: 87.5,
+                memory_bytes: 1200 << 20,
+                command: "postgres: app app 172.18.0.4(51234) SELECT".to_string(),
+            },
+            ProcessInfo {
+                pid: 1187,
+                name: "nginx".to_string(),
+                user: "www-data".to_string(),
+                cpu_percent: 12.0,
+                memory_bytes: 48 << 20,
+                command: "nginx: worker process".to_string(),
+            },
+        ],
+        top_memory_processes: vec![ProcessInfo {
+            pid: 2314,
+            name: "postgres".to_string(),
+            user: "postgres".to_string(),
+            cpu_percent: 87.5,
+            memory_bytes: 1200 << 20,
+            command: "postgres: app app 172.18.0.4(51234) SELECT".to_string(),
+        }],
+        inventory: Some(HostInventory {
+            os_name: "Debian GNU/Linux".to_string(),
+            os_version: "12".to_string(),
+            distribution_id: "debian".to_string(),
+            kernel_version: "6.1.0-25-amd64".to_string(),
+            architecture: "x86_64".to_string(),
+            cpu_model: "Intel(R) Xeon(R) CPU".to_string(),
+            logical_cores: 4,
+            physical_cores: 2,
+            total_memory_bytes: 16 << 30,
+            virtualization: "kvm".to_string(),
+            container: String::new(),
+            reporter_version: env!("CARGO_PKG_VERSION").to_string(),
+        }),
+        package_updates: Some(PackageUpdates {
+            manager: "apt".to_string(),
+            pending: 3,
+            security: 1,
+            reboot_required: true,
+            packages: vec![
+                "openssl".to_string(),
+                "curl".to_string(),
+                "tzdata".to_string(),
+            ],
+            checked_at: timestamp as i64 - 1800,
+        }),
+        custom_metrics: vec![
+            CustomMetric {
+                name: "ups.charge".to_string(),
+                value: 98.0,
+                unit: "%".to_string(),
+            },
+            CustomMetric {
+                name: "backup.age".to_string(),
+                value: 93_600.0,
+                unit: "s".to_string(),
+            },
+        ],
+        checks: vec![
+            CheckResult {
+                name: "backup".to_string(),
+                status: CheckStatus::Warning.into(),
+                message: "last backup 26h ago".to_string(),
+                checked_at: timestamp as i64 - 30,
+                latency_ms: 0.0,
+            },
+            CheckResult {
+                name: "ups".to_string(),
+                status: CheckStatus::Ok.into(),
+                message: "on mains".to_string(),
+                checked_at: timestamp as i64 - 10,
+                latency_ms: 0.0,
+            },
+        ],
+        certificates: vec![
+            Certificate {
+                source: "test-server.local:443".to_string(),
+                subject: "CN=test-server.local".to_string(),
+                issuer: "CN=R11, O=Let's Encrypt, C=US".to_string(),
+                sans: vec![
+                    "test-server.local".to_string(),
+                    "www.test-server.local".to_string(),
+                ],
+                not_before: timestamp as i64 - 30 * 86_400,
+                not_after: timestamp as i64 + 60 * 86_400,
+                error: String::new(),
+                checked_at: timestamp as i64 - 60,
+            },
+            Certificate {
+                source: "/etc/ssl/certs/internal-ca.pem".to_string(),
+                subject: "CN=Internal CA".to_string(),
+                issuer: "CN=Internal CA".to_string(),
+                sans: Vec::new(),
+                not_before: timestamp as i64 - 400 * 86_400,
+                not_after: timestamp as i64 + 3250 * 86_400,
+                error: String::new(),
+                checked_at: timestamp as i64 - 60,
+            },
+        ],
         services: vec![
             Service {
                 name: "nginx".to_string(),
                 status: "running".to_string(),
                 needs_update: false,
+                image: "nginx:1.27".to_string(),
+                kind: ServiceKind::Container.into(),
+                health: ServiceHealth::Healthy.into(),
+                started_at: timestamp as i64 - 7200,
+                version: "1.27".to_string(),
+                ports: vec!["8080->80/tcp".to_string()],
+                labels: [("com.docker.compose.project".to_string(), "web".to_string())].into(),
+                probes: vec![CheckResult {
+                    name: "nginx".to_string(),
+                    status: CheckStatus::Ok.into(),
+                    message: "HTTP 200 OK".to_string(),
+                    checked_at: timestamp as i64 - 5,
+                    latency_ms: 12.4,
+                }],
+                ..Default::default()
             },
             Service {
                 name: "postgres".to_string(),
                 status: "stopped".to_string(),
                 needs_update: true,
+                image: "postgres:16".to_string(),
+                kind: ServiceKind::Container.into(),
+                probes: vec![CheckResult {
+                    name: "postgres".to_string(),
+                    status: CheckStatus::Critical.into(),
+                    message: "Connection refused (os error 111)".to_string(),
+                    checked_at: timestamp as i64 - 5,
+                    latency_ms: 0.3,
+                }],
+                ..Default::default()
+            },
+            Service {
+                name: "backup.service".to_string(),
+                status: "failed".to_string(),
+                sub_state: "failed".to_string(),
+                restart_count: 3,
+                kind: ServiceKind::Systemd.into(),
+                ..Default::default()
             },
         ],
     };
 
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let entry = ReportEntry {
-        report: test_report,
-        last_updated: timestamp,
-    };
-
-    state.lock().unwrap().update_report(entry);
+    state
+        .send(StateEvent::Report(ReportEntry::received(test_report)))
+        .await;
     Ok(warp::reply::with_status(
         "Test client added",
         warp::http::StatusCode::OK,
@@ -62,9 +450,17 @@ async fn serve_dashboard() -> Result<impl warp::Reply, warp::Rejection> {
     )))
 }
 
+/// Port the web server listens on, `SWARM_WEB_PORT` or 6969
+pub fn web_port() -> u16 {
+    std::env::var("SWARM_WEB_PORT")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(6969)
+}
+
 /// Starts the web server with API endpoints and dashboard
 pub async fn run_web_server(
-    state: SharedState,
+    state: StateHandle,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let state_filter = warp::any().map(move || state.clone());
 
@@ -74,6 +470,65 @@ pub async fn run_web_server(
         .and(state_filter.clone())
         .and_then(get_clients_api);
 
+    let api_outdated = warp::path!("api" / "outdated")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_outdated_api);
+
+    let api_swarm = warp::path!("api" / "swarm")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_swarm_api);
+
+    let api_inventory = warp::path!("api" / "inventory")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_inventory_api);
+
+    let api_inventory_csv = warp::path!("api" / "inventory.csv")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_inventory_csv);
+
+    let api_patches = warp::path!("api" / "patches")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_patches_api);
+
+    let api_certificates = warp::path!("api" / "certificates")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_certificates_api);
+
+    let api_tailnet = warp::path!("api" / "tailnet")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_tailnet_api);
+
+    let api_sites = warp::path!("api" / "sites")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_sites_api);
+
+    let api_ha = warp::path!("api" / "ha")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_ha_api);
+
+    let api_state = warp::path!("api" / "state")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_state_api);
+
+    let api_alerts = warp::path!("api" / "alerts")
+        .and(warp::get())
+        .and(state_filter.clone())
+        .and_then(get_alerts_api);
+
+    let api_settings = warp::path!("api" / "settings")
+        .and(warp::get())
+        .and_then(get_settings_api);
+
     let test_client = warp::path!("api" / "test")
         .and(warp::post())
         .and(state_filter)
@@ -81,9 +536,23 @@ pub async fn run_web_server(
 
     let dashboard = warp::path::end().and(warp::get()).and_then(serve_dashboard);
 
-    let routes = dashboard.or(api_clients).or(test_client);
+    let routes = dashboard
+        .or(api_clients)
+        .or(api_outdated)
+        .or(api_swarm)
+        .or(api_inventory)
+        .or(api_inventory_csv)
+        .or(api_patches)
+        .or(api_certificates)
+        .or(api_tailnet)
+        .or(api_sites)
+        .or(api_ha)
+        .or(api_state)
+        .or(api_alerts)
+        .or(api_settings)
+        .or(test_client);
 
-    println!("Web dashboard available at http://localhost:6969");
-    warp::serve(routes).run(([0, 0, 0, 0], 6969)).await;
+    println!("Web dashboard available at http://localhost:{}", web_port());
+    warp::serve(routes).run(([0, 0, 0, 0], web_port())).await;
     Ok(())
 }